@@ -0,0 +1,175 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    spanned::Spanned, Data, DeriveInput, Error, Field, Fields, Lit, Meta, NestedMeta, Path, Result,
+};
+
+/// 字段的来源
+enum Source {
+    /// 字段类型自身实现了 `FromRequest`
+    Request,
+    /// `Source::$variant` 中按名字查找
+    Named(&'static str, String),
+    Body,
+}
+
+enum DefaultValue {
+    None,
+    Trait,
+    Fn(Path),
+}
+
+struct FieldAttrs {
+    source: Source,
+    default: DefaultValue,
+}
+
+impl FieldAttrs {
+    fn parse(field: &Field) -> Result<Self> {
+        let mut attrs = FieldAttrs {
+            source: Source::Request,
+            default: DefaultValue::None,
+        };
+        let ident = field.ident.as_ref().map(ToString::to_string);
+        for attr in field.attrs.iter() {
+            if !attr.path.is_ident("from_request") {
+                continue;
+            }
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => return Err(Error::new(meta.span(), "expected `#[from_request(...)]`")),
+            };
+            for nested in list.nested.iter() {
+                let meta = match nested {
+                    NestedMeta::Meta(meta) => meta,
+                    NestedMeta::Lit(lit) => {
+                        return Err(Error::new(lit.span(), "unexpected literal"))
+                    }
+                };
+                let key = meta
+                    .path()
+                    .get_ident()
+                    .map(ToString::to_string)
+                    .unwrap_or_default();
+                let value = match meta {
+                    Meta::Path(_) => None,
+                    Meta::NameValue(nv) => match &nv.lit {
+                        Lit::Str(s) => Some(s.clone()),
+                        lit => return Err(Error::new(lit.span(), "expected a string literal")),
+                    },
+                    Meta::List(list) => return Err(Error::new(list.span(), "unexpected list")),
+                };
+                let variant = match key.as_str() {
+                    "path" => "Path",
+                    "query" => "Query",
+                    "header" => "Header",
+                    "body" => {
+                        if let Some(value) = value {
+                            return Err(Error::new(value.span(), "`body` takes no name"));
+                        }
+                        attrs.set_source(meta, Source::Body)?;
+                        continue;
+                    }
+                    "default" => {
+                        if !matches!(attrs.default, DefaultValue::None) {
+                            return Err(Error::new(meta.span(), "duplicate `default`"));
+                        }
+                        attrs.default = match value {
+                            Some(value) => DefaultValue::Fn(value.parse()?),
+                            None => DefaultValue::Trait,
+                        };
+                        continue;
+                    }
+                    _ => return Err(Error::new(meta.span(), "unknown `from_request` attribute")),
+                };
+                let name =
+                    match (value, &ident) {
+                        (Some(value), _) => value.value(),
+                        (None, Some(ident)) => ident.clone(),
+                        (None, None) => return Err(Error::new(
+                            meta.span(),
+                            "tuple struct fields need an explicit name, e.g. `query = \"limit\"`",
+                        )),
+                    };
+                attrs.set_source(meta, Source::Named(variant, name))?;
+            }
+        }
+        Ok(attrs)
+    }
+
+    fn set_source(&mut self, meta: &Meta, source: Source) -> Result<()> {
+        if !matches!(self.source, Source::Request) {
+            return Err(Error::new(meta.span(), "a field can only have one source"));
+        }
+        self.source = source;
+        Ok(())
+    }
+}
+
+fn expand_field(krate: &TokenStream, field: &Field) -> Result<TokenStream> {
+    let attrs = FieldAttrs::parse(field)?;
+    let ty = &field.ty;
+    let extract = match &attrs.source {
+        Source::Request => quote! {
+            <#ty as #krate::FromRequest>::from_request(req).map_err(::std::convert::Into::<#krate::ExtractError>::into)
+        },
+        Source::Named(variant, name) => {
+            let variant = format_ident!("{}", variant);
+            quote! { #krate::Source::#variant.extract::<#ty>(req, #name) }
+        }
+        Source::Body => quote! { #krate::Source::Body.extract::<#ty>(req, "") },
+    };
+    Ok(match &attrs.default {
+        DefaultValue::None => quote! { (#extract)? },
+        DefaultValue::Trait => quote! { (#extract).unwrap_or_default() },
+        DefaultValue::Fn(path) => quote! { (#extract).unwrap_or_else(|_| #path()) },
+    })
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let krate = quote! { ::type_erase::async_with_return };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "`FromRequest` can only be derived for structs",
+            ))
+        }
+    };
+    let body = match fields {
+        Fields::Named(named) => {
+            let fields = named
+                .named
+                .iter()
+                .map(|field| {
+                    let ident = &field.ident;
+                    let value = expand_field(&krate, field)?;
+                    Ok(quote! { #ident: #value })
+                })
+                .collect::<Result<Vec<_>>>()?;
+            quote! { Self { #(#fields,)* } }
+        }
+        Fields::Unnamed(unnamed) => {
+            let fields = unnamed
+                .unnamed
+                .iter()
+                .map(|field| expand_field(&krate, field))
+                .collect::<Result<Vec<_>>>()?;
+            quote! { Self ( #(#fields,)* ) }
+        }
+        Fields::Unit => quote! { Self },
+    };
+
+    Ok(quote! {
+        impl #impl_generics #krate::FromRequest for #name #ty_generics #where_clause {
+            type Error = #krate::ExtractError;
+            fn from_request(req: &#krate::Request) -> ::std::result::Result<Self, Self::Error> {
+                ::std::result::Result::Ok(#body)
+            }
+        }
+    })
+}
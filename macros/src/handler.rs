@@ -0,0 +1,116 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{
+    ext::IdentExt, spanned::Spanned, AttributeArgs, Error, FnArg, ItemFn, Lit, Meta, NestedMeta,
+    Result, ReturnType,
+};
+
+struct HandlerArgs {
+    name: Option<String>,
+    route: Option<String>,
+    method: Option<String>,
+}
+
+impl HandlerArgs {
+    fn parse(args: AttributeArgs) -> Result<Self> {
+        let mut parsed = HandlerArgs {
+            name: None,
+            route: None,
+            method: None,
+        };
+        for arg in args {
+            let nv = match arg {
+                NestedMeta::Meta(Meta::NameValue(nv)) => nv,
+                arg => return Err(Error::new(arg.span(), "expected `key = \"value\"`")),
+            };
+            let value = match &nv.lit {
+                Lit::Str(s) => s.value(),
+                lit => return Err(Error::new(lit.span(), "expected a string literal")),
+            };
+            let slot = match nv.path.get_ident().map(ToString::to_string).as_deref() {
+                Some("name") => &mut parsed.name,
+                Some("route") => &mut parsed.route,
+                Some("method") => &mut parsed.method,
+                _ => return Err(Error::new(nv.path.span(), "unknown `handler` attribute")),
+            };
+            if slot.replace(value).is_some() {
+                return Err(Error::new(nv.path.span(), "duplicate `handler` attribute"));
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+pub fn expand(args: AttributeArgs, item: ItemFn) -> Result<TokenStream> {
+    let krate = quote! { ::type_erase::async_with_return };
+    let args = HandlerArgs::parse(args)?;
+
+    let sig = &item.sig;
+    if sig.asyncness.is_none() {
+        return Err(Error::new(
+            sig.fn_token.span(),
+            "`#[handler]` requires an `async fn`",
+        ));
+    }
+    if !sig.generics.params.is_empty() || sig.generics.where_clause.is_some() {
+        return Err(Error::new(
+            sig.generics.span(),
+            "`#[handler]` functions cannot be generic",
+        ));
+    }
+
+    let vis = &item.vis;
+    let ident = &sig.ident;
+    let name = args.name.unwrap_or_else(|| ident.to_string());
+    let types = sig
+        .inputs
+        .iter()
+        .map(|arg| match arg {
+            FnArg::Typed(pat) => Ok(&pat.ty),
+            FnArg::Receiver(recv) => {
+                Err(Error::new(recv.span(), "`#[handler]` cannot take `self`"))
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let indices = (0..types.len()).map(syn::Index::from);
+    let output = match &sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) => quote! { #ty },
+    };
+    let route = args.route.map(|route| quote! { .with_route(#route) });
+    let method = args.method.map(|method| quote! { .with_method(#method) });
+
+    let future = quote! {
+        ::std::pin::Pin<::std::boxed::Box<dyn ::std::future::Future<Output = #output>>>
+    };
+    let handler = format_ident!("{}Handler", camel_case(&ident.unraw().to_string()));
+    let doc = format!("`{}` 的 handler，通过 `App::handler` 注册", ident);
+    Ok(quote! {
+        #item
+
+        #[doc = #doc]
+        #[derive(Clone, Copy)]
+        #vis struct #handler;
+
+        impl #krate::Handler<( #(#types,)* ), #future> for #handler {
+            fn call(&self, param: ( #(#types,)* )) -> #future {
+                ::std::boxed::Box::pin(#ident( #(param.#indices,)* ))
+            }
+            fn info(&self) -> #krate::HandlerInfo {
+                #krate::HandlerInfo::new(#name) #route #method
+            }
+        }
+    })
+}
+
+/// `create_user` -> `CreateUser`
+fn camel_case(name: &str) -> String {
+    name.split('_')
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let mut chars = word.chars();
+            let first = chars.next().unwrap().to_ascii_uppercase();
+            first.to_string() + chars.as_str()
+        })
+        .collect()
+}
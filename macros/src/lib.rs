@@ -0,0 +1,62 @@
+//! `type_erase` 的过程宏
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, AttributeArgs, DeriveInput, ItemFn};
+
+mod from_request;
+mod handler;
+mod validate;
+
+/// 当结构体的所有字段都可以从请求中提取时，为其实现 `FromRequest`。
+///
+/// 字段属性 `#[from_request(...)]` 可以选择来源和默认值：
+///
+/// - `path`、`query`、`header`：按名字取出对应的值并通过 `FromStr` 解析，
+///   名字默认为字段名，也可以写成 `query = "limit"`
+/// - `body`：通过 `FromStr` 解析整个请求体
+/// - `default` / `default = "path::to::fn"`：提取失败时使用默认值
+///
+/// 没有指定来源的字段通过字段类型自身的 `FromRequest` 提取。
+#[proc_macro_derive(FromRequest, attributes(from_request))]
+pub fn derive_from_request(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    from_request::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// 为 `async fn` 生成注册所需的 `Handler` 实现，并附带名字、路由和方法。
+///
+/// ```ignore
+/// #[handler(name = "create_user", route = "/users", method = "POST")]
+/// async fn create_user(name: String) {}
+///
+/// App::new().handler(CreateUserHandler);
+/// ```
+///
+/// 函数本身保持不变，可以直接调用和测试；注册时使用旁边生成的单元结构体，
+/// 名字为函数名转为驼峰后加上 `Handler`。所有参数都可省略，名字默认为函数名。
+#[proc_macro_attribute]
+pub fn handler(args: TokenStream, input: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(args as AttributeArgs);
+    let input = parse_macro_input!(input as ItemFn);
+    handler::expand(args, input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+/// 为结构体实现 `Validate`，字段属性 `#[validate(...)]` 支持以下规则：
+///
+/// - `min = 1`、`max = 10`：数值范围，包含边界
+/// - `length(min = 1, max = 32)`：字符串的字符数或集合的元素数
+/// - `non_empty`：长度不为零
+/// - `custom = "path::to::fn"`：调用 `fn(&T) -> Result<(), String>`
+///
+/// 所有规则都会检查，失败的规则全部收集到 `ValidationErrors` 中。
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn derive_validate(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    validate::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
@@ -0,0 +1,155 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    spanned::Spanned, Data, DeriveInput, Error, Field, Fields, Index, Lit, Meta, NestedMeta, Result,
+};
+
+/// 数值规则的右侧只接受整数或浮点数字面量
+fn number(lit: &Lit) -> Result<Lit> {
+    match lit {
+        Lit::Int(_) | Lit::Float(_) => Ok(lit.clone()),
+        lit => Err(Error::new(lit.span(), "expected a number")),
+    }
+}
+
+fn expand_rule(
+    krate: &TokenStream,
+    name: &str,
+    value: &TokenStream,
+    meta: &Meta,
+) -> Result<TokenStream> {
+    let key = meta
+        .path()
+        .get_ident()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    Ok(match (key.as_str(), meta) {
+        ("min", Meta::NameValue(nv)) => {
+            let min = number(&nv.lit)?;
+            quote! {
+                if #value < #min {
+                    errors.add(#name, "min", ::std::format!("must be at least {}", #min));
+                }
+            }
+        }
+        ("max", Meta::NameValue(nv)) => {
+            let max = number(&nv.lit)?;
+            quote! {
+                if #value > #max {
+                    errors.add(#name, "max", ::std::format!("must be at most {}", #max));
+                }
+            }
+        }
+        ("non_empty", Meta::Path(_)) => quote! {
+            if #krate::Length::length(&#value) == 0 {
+                errors.add(#name, "non_empty", "must not be empty");
+            }
+        },
+        ("length", Meta::List(list)) => {
+            let mut checks = vec![];
+            for nested in list.nested.iter() {
+                let nv = match nested {
+                    NestedMeta::Meta(Meta::NameValue(nv)) => nv,
+                    nested => return Err(Error::new(nested.span(), "expected `min` or `max`")),
+                };
+                let bound = match &nv.lit {
+                    Lit::Int(int) => int.base10_parse::<usize>()?,
+                    lit => return Err(Error::new(lit.span(), "expected an integer")),
+                };
+                checks.push(match nv.path.get_ident().map(ToString::to_string).as_deref() {
+                    Some("min") => quote! {
+                        if len < #bound {
+                            errors.add(#name, "length", ::std::format!("length must be at least {}", #bound));
+                        }
+                    },
+                    Some("max") => quote! {
+                        if len > #bound {
+                            errors.add(#name, "length", ::std::format!("length must be at most {}", #bound));
+                        }
+                    },
+                    _ => return Err(Error::new(nv.path.span(), "expected `min` or `max`")),
+                });
+            }
+            quote! {
+                {
+                    let len = #krate::Length::length(&#value);
+                    #(#checks)*
+                }
+            }
+        }
+        ("custom", Meta::NameValue(nv)) => {
+            let path: syn::Path = match &nv.lit {
+                Lit::Str(s) => s.parse()?,
+                lit => return Err(Error::new(lit.span(), "expected a string literal")),
+            };
+            quote! {
+                if let ::std::result::Result::Err(message) = #path(&#value) {
+                    errors.add(#name, "custom", message);
+                }
+            }
+        }
+        _ => return Err(Error::new(meta.span(), "unknown `validate` rule")),
+    })
+}
+
+fn expand_field(krate: &TokenStream, index: usize, field: &Field) -> Result<TokenStream> {
+    let (name, value) = match &field.ident {
+        Some(ident) => (ident.to_string(), quote! { self.#ident }),
+        None => {
+            let index = Index::from(index);
+            (index.index.to_string(), quote! { self.#index })
+        }
+    };
+    let mut rules = vec![];
+    for attr in field.attrs.iter() {
+        if !attr.path.is_ident("validate") {
+            continue;
+        }
+        let list = match attr.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(Error::new(meta.span(), "expected `#[validate(...)]`")),
+        };
+        for nested in list.nested.iter() {
+            match nested {
+                NestedMeta::Meta(meta) => rules.push(expand_rule(krate, &name, &value, meta)?),
+                NestedMeta::Lit(lit) => return Err(Error::new(lit.span(), "unexpected literal")),
+            }
+        }
+    }
+    Ok(quote! { #(#rules)* })
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+    let krate = quote! { ::type_erase::async_with_return };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "`Validate` can only be derived for structs",
+            ))
+        }
+    };
+    let checks = match fields {
+        Fields::Named(named) => named.named.iter().collect::<Vec<_>>(),
+        Fields::Unnamed(unnamed) => unnamed.unnamed.iter().collect(),
+        Fields::Unit => vec![],
+    }
+    .into_iter()
+    .enumerate()
+    .map(|(i, field)| expand_field(&krate, i, field))
+    .collect::<Result<Vec<_>>>()?;
+
+    Ok(quote! {
+        impl #impl_generics #krate::Validate for #name #ty_generics #where_clause {
+            fn validate(&self) -> ::std::result::Result<(), #krate::ValidationErrors> {
+                let mut errors = #krate::ValidationErrors::new();
+                #(#checks)*
+                errors.into_result()
+            }
+        }
+    })
+}
@@ -0,0 +1,74 @@
+/// 对 1 到 16 个参数依次调用 `$m!((T1, T2, ..), (0, 1, ..))`。
+///
+/// 同步和异步的 `Handler`、`FromRequest` 元组实现都由它展开，保证支持的参数个数一致。
+#[rustfmt::skip]
+macro_rules! all_tuples {
+    ($m:ident) => {
+        $m!((T1), (0));
+        $m!((T1, T2), (0, 1));
+        $m!((T1, T2, T3), (0, 1, 2));
+        $m!((T1, T2, T3, T4), (0, 1, 2, 3));
+        $m!((T1, T2, T3, T4, T5), (0, 1, 2, 3, 4));
+        $m!((T1, T2, T3, T4, T5, T6), (0, 1, 2, 3, 4, 5));
+        $m!((T1, T2, T3, T4, T5, T6, T7), (0, 1, 2, 3, 4, 5, 6));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8), (0, 1, 2, 3, 4, 5, 6, 7));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9), (0, 1, 2, 3, 4, 5, 6, 7, 8));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15));
+    };
+}
+
+/// 与 `all_tuples!` 相同，但把最后一个类型单独传入：`$m!((T1, ..), Tn, (0, ..), n - 1)`。
+///
+/// 用于只约束最后一个参数的实现，如只有最后一个参数可以取走请求体。
+#[rustfmt::skip]
+macro_rules! all_tuples_with_last {
+    ($m:ident) => {
+        $m!((), T1, (), 0);
+        $m!((T1), T2, (0), 1);
+        $m!((T1, T2), T3, (0, 1), 2);
+        $m!((T1, T2, T3), T4, (0, 1, 2), 3);
+        $m!((T1, T2, T3, T4), T5, (0, 1, 2, 3), 4);
+        $m!((T1, T2, T3, T4, T5), T6, (0, 1, 2, 3, 4), 5);
+        $m!((T1, T2, T3, T4, T5, T6), T7, (0, 1, 2, 3, 4, 5), 6);
+        $m!((T1, T2, T3, T4, T5, T6, T7), T8, (0, 1, 2, 3, 4, 5, 6), 7);
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8), T9, (0, 1, 2, 3, 4, 5, 6, 7), 8);
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9), T10, (0, 1, 2, 3, 4, 5, 6, 7, 8), 9);
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10), T11, (0, 1, 2, 3, 4, 5, 6, 7, 8, 9), 10);
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11), T12, (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10), 11);
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12), T13, (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11), 12);
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13), T14, (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12), 13);
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14), T15, (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13), 14);
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15), T16, (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14), 15);
+    };
+}
+
+/// 与 `all_tuples!` 相同，但为每个类型附带一个标记参数：`$m!((T1, ..), (M1, ..), (0, ..))`。
+///
+/// 用于每个元素通过各自的标记实现同一个 trait 的情况，如 `OneOf` 的备选。
+#[rustfmt::skip]
+macro_rules! all_tuples_with_markers {
+    ($m:ident) => {
+        $m!((T1), (M1), (0));
+        $m!((T1, T2), (M1, M2), (0, 1));
+        $m!((T1, T2, T3), (M1, M2, M3), (0, 1, 2));
+        $m!((T1, T2, T3, T4), (M1, M2, M3, M4), (0, 1, 2, 3));
+        $m!((T1, T2, T3, T4, T5), (M1, M2, M3, M4, M5), (0, 1, 2, 3, 4));
+        $m!((T1, T2, T3, T4, T5, T6), (M1, M2, M3, M4, M5, M6), (0, 1, 2, 3, 4, 5));
+        $m!((T1, T2, T3, T4, T5, T6, T7), (M1, M2, M3, M4, M5, M6, M7), (0, 1, 2, 3, 4, 5, 6));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8), (M1, M2, M3, M4, M5, M6, M7, M8), (0, 1, 2, 3, 4, 5, 6, 7));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9), (M1, M2, M3, M4, M5, M6, M7, M8, M9), (0, 1, 2, 3, 4, 5, 6, 7, 8));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10), (M1, M2, M3, M4, M5, M6, M7, M8, M9, M10), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11), (M1, M2, M3, M4, M5, M6, M7, M8, M9, M10, M11), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12), (M1, M2, M3, M4, M5, M6, M7, M8, M9, M10, M11, M12), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13), (M1, M2, M3, M4, M5, M6, M7, M8, M9, M10, M11, M12, M13), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14), (M1, M2, M3, M4, M5, M6, M7, M8, M9, M10, M11, M12, M13, M14), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15), (M1, M2, M3, M4, M5, M6, M7, M8, M9, M10, M11, M12, M13, M14, M15), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14));
+        $m!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15, T16), (M1, M2, M3, M4, M5, M6, M7, M8, M9, M10, M11, M12, M13, M14, M15, M16), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15));
+    };
+}
@@ -0,0 +1,1562 @@
+use std::{
+    any::type_name,
+    future::Future,
+    marker::PhantomData,
+    panic::{self as std_panic, AssertUnwindSafe},
+    pin::Pin,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+mod body;
+mod cancel;
+mod circuit;
+mod context;
+mod cookie;
+mod either;
+mod error;
+mod extract;
+mod form;
+mod header;
+mod info;
+mod json;
+mod limit;
+mod list;
+mod log;
+mod multipart;
+mod panic;
+mod query;
+mod queue;
+mod report;
+mod request;
+mod response;
+mod retry;
+mod scope;
+mod timer;
+pub mod urlencoded;
+mod validate;
+
+pub use body::{Body, Chunks};
+pub use cancel::{CancellationToken, Cancelled};
+pub use circuit::{CircuitBreaker, CircuitState};
+pub use context::AppContext;
+pub use cookie::{Cookie, CookieError, CookieKey, Cookies, SameSite};
+pub use either::{Alternatives, Either, OneOf};
+pub use error::DispatchError;
+pub use extract::{
+    BoxError, ExtractError, ExtractFuture, FromRequest, FromRequestBody, FromRequestParts,
+    HandlerParams, IntegerConfig, Parsed, Source, StringConfig, ViaBody, ViaParts,
+};
+pub use form::Form;
+pub use header::{
+    Authorization, ContentLength, ContentType, Header, Headers, TypedHeader, UserAgent,
+};
+pub use info::HandlerInfo;
+pub use json::{Json, JsonConfig};
+pub use limit::{ConcurrencyLimit, LimitKey, RateLimit};
+pub use list::{Comma, List, ListConfig, Pipe, Semicolon, Separator, Split, Whitespace};
+pub use log::{LogFormat, Logger};
+pub use multipart::{Multipart, MultipartConfig, MultipartStream, Part};
+pub use panic::{CatchUnwind, PanicPolicy};
+pub use query::Query;
+pub use queue::{DispatchQueue, Overflow, QueueError, QueueMetrics};
+use report::Trace;
+pub use report::{Attempt, DispatchReport, HandlerReport, Outcome};
+pub use request::{Extensions, Request};
+pub use response::{Responder, Response};
+pub use retry::RetryPolicy;
+pub use timer::{sleep, timeout, Sleep, Timeout};
+pub use type_erase_macros::{handler, FromRequest, Validate};
+pub use validate::{FieldError, Length, Valid, Validate, ValidationErrors};
+
+type ErrorHandler = Box<dyn Fn(&DispatchError, &Request) -> Response>;
+
+pub struct App {
+    services: Vec<scope::Entry>,
+    /// 分发时随请求一起交给提取器
+    context: Arc<AppContext>,
+    /// 没有设置时使用外层 scope 的设置或默认值
+    panic_policy: Option<PanicPolicy>,
+    default_timeout: Option<Duration>,
+    /// 把失败转换为响应，见 `App::on_error`
+    error_handler: Option<ErrorHandler>,
+    /// 熔断器的共享状态，用于 `App::circuits`
+    circuits: Vec<(HandlerInfo, Arc<circuit::Circuit>)>,
+    /// 所有 handler 共享的限流，见 `App::shared_rate_limit`
+    limiters: Vec<Box<dyn limit::Limiter>>,
+    logger: Option<Logger>,
+}
+impl App {
+    pub fn new() -> Self {
+        Self {
+            services: vec![],
+            context: Arc::default(),
+            panic_policy: None,
+            default_timeout: None,
+            error_handler: None,
+            circuits: vec![],
+            limiters: vec![],
+            logger: None,
+        }
+    }
+    pub fn handler<F, T, R, M>(mut self, f: F) -> Self
+    where
+        F: Handler<T, R>,
+        T: HandlerParams<M> + 'static,
+        R: Future + 'static,
+        R::Output: Responder,
+        M: 'static,
+    {
+        let info = f.info();
+        self.services
+            .push(scope::Entry::new(info, Box::new(ServiceWrapper::new(f))));
+        self
+    }
+    /// 与 `handler` 相同，参数可以 `Clone` 时 `RetryPolicy::reuse_params` 可以在重试时复用它们
+    pub fn reusable_handler<F, T, R, M>(mut self, f: F) -> Self
+    where
+        F: Handler<T, R>,
+        T: HandlerParams<M> + Clone + 'static,
+        R: Future + 'static,
+        R::Output: Responder,
+        M: 'static,
+    {
+        let info = f.info();
+        let service = Box::new(ServiceWrapper::reusing_params(f));
+        let mut entry = scope::Entry::new(info, service);
+        entry.reusable_params = true;
+        self.services.push(entry);
+        self
+    }
+    /// 为最近注册的 handler 设置超时，超时后放弃它并取消其 `CancellationToken`
+    ///
+    /// 设置了重试时每次尝试分别计时
+    pub fn timeout(mut self, duration: Duration) -> Self {
+        let entry = self
+            .services
+            .last_mut()
+            .expect("`App::timeout` must follow `App::handler`");
+        entry.timeout = Some(duration);
+        self
+    }
+    /// 为最近注册的 handler 设置重试，见 `RetryPolicy`
+    pub fn retry(self, policy: RetryPolicy) -> Self {
+        if policy.reuses_params() {
+            let reusable = self.services.last().is_some_and(|e| e.reusable_params);
+            assert!(
+                reusable,
+                "`RetryPolicy::reuse_params` requires `App::reusable_handler`"
+            );
+        }
+        self.wrap_last("retry", |service| {
+            Box::new(retry::Retry::new(service, policy))
+        })
+    }
+    /// 为最近注册的 handler 设置熔断器，见 `CircuitBreaker`
+    pub fn circuit_breaker(mut self, breaker: CircuitBreaker) -> Self {
+        let circuit = Arc::new(circuit::Circuit::new(breaker));
+        if let Some(entry) = self.services.last() {
+            self.circuits.push((entry.info.clone(), circuit.clone()));
+        }
+        self.wrap_last("circuit_breaker", |service| {
+            Box::new(circuit::CircuitService::new(service, circuit))
+        })
+    }
+    /// 为最近注册的 handler 设置限流，见 `RateLimit`
+    pub fn rate_limit<K: LimitKey>(self, limit: RateLimit<K>) -> Self {
+        self.wrap_last("rate_limit", |service| {
+            let limiter = Box::new(limit::RateLimiter::new(limit));
+            Box::new(limit::LimitService::new(service, limiter))
+        })
+    }
+    /// 为最近注册的 handler 设置并发上限，见 `ConcurrencyLimit`
+    pub fn concurrency_limit<K: LimitKey>(self, limit: ConcurrencyLimit<K>) -> Self {
+        self.wrap_last("concurrency_limit", |service| {
+            let limiter = Box::new(limit::ConcurrencyLimiter::new(limit));
+            Box::new(limit::LimitService::new(service, limiter))
+        })
+    }
+    /// 为这个 `App` 中的所有 handler 设置共享的限流，每次调用 handler 消耗一个令牌
+    ///
+    /// 在 `App::scope` 中设置时只由其中的 handler 共享
+    pub fn shared_rate_limit<K: LimitKey>(mut self, limit: RateLimit<K>) -> Self {
+        self.limiters.push(Box::new(limit::RateLimiter::new(limit)));
+        self
+    }
+    /// 为这个 `App` 中的所有 handler 设置共享的并发上限
+    ///
+    /// 在 `App::scope` 中设置时只由其中的 handler 共享
+    pub fn shared_concurrency_limit<K: LimitKey>(mut self, limit: ConcurrencyLimit<K>) -> Self {
+        self.limiters
+            .push(Box::new(limit::ConcurrencyLimiter::new(limit)));
+        self
+    }
+    /// 设置了熔断器的 handler 及其当前状态
+    pub fn circuits(&self) -> impl Iterator<Item = (&HandlerInfo, CircuitState)> {
+        self.circuits
+            .iter()
+            .map(|(info, circuit)| (info, circuit.state()))
+    }
+    /// 用中间件包装最近注册的 handler
+    fn wrap_last<F>(mut self, method: &str, wrap: F) -> Self
+    where
+        F: FnOnce(Box<dyn Service>) -> Box<dyn Service>,
+    {
+        let mut entry = self
+            .services
+            .pop()
+            .unwrap_or_else(|| panic!("`App::{}` must follow `App::handler`", method));
+        entry.service = wrap(entry.service);
+        self.services.push(entry);
+        self
+    }
+    /// 没有单独设置超时的 handler 使用的超时，默认不限制
+    pub fn default_timeout(mut self, duration: Duration) -> Self {
+        self.default_timeout = Some(duration);
+        self
+    }
+    /// 已注册的 handler 的元信息
+    pub fn handlers(&self) -> impl Iterator<Item = &HandlerInfo> {
+        self.services.iter().map(|entry| &entry.info)
+    }
+    /// 注册提取器的配置，如 `JsonConfig`、`ListConfig`、`CookieKey`，同类型的配置只保留最后一次
+    ///
+    /// 在 `App::scope` 中注册的配置只作用于其中的 handler，并优先于外层的配置
+    pub fn extractor_config<C: Send + Sync + 'static>(mut self, config: C) -> Self {
+        Arc::make_mut(&mut self.context).insert(config);
+        self
+    }
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = Some(policy);
+        self
+    }
+    /// 在路由前缀 `prefix` 之下注册一组 handler，前缀中同样可以用 `:name` 捕获参数
+    ///
+    /// `f` 在一个新的 `App` 上注册这组 handler 及其中间件；其上的 `extractor_config`、
+    /// `default_timeout`、`panic_policy`、`on_error` 和共享的限流只作用于这组 handler，没有设置的项沿用外层。
+    /// scope 可以嵌套
+    pub fn scope<F>(mut self, prefix: &str, f: F) -> Self
+    where
+        F: FnOnce(App) -> App,
+    {
+        let nested = f(App::new());
+        assert!(
+            nested.logger.is_none(),
+            "`App::logger` cannot be set on a scope"
+        );
+        let scope = Rc::new(scope::Scope {
+            context: nested.context,
+            default_timeout: nested.default_timeout,
+            panic_policy: nested.panic_policy,
+            error_handler: nested.error_handler,
+            limiters: nested.limiters,
+        });
+        for mut entry in nested.services {
+            entry.info = entry.info.with_prefix(prefix);
+            entry.scopes.push(scope.clone());
+            self.services.push(entry);
+        }
+        let circuits = nested.circuits.into_iter();
+        self.circuits
+            .extend(circuits.map(|(info, circuit)| (info.with_prefix(prefix), circuit)));
+        self
+    }
+    /// 提取或 handler 失败时由 `f` 生成响应，可以通过 `DispatchError::downcast_ref` 区分错误类型
+    ///
+    /// 在 `App::scope` 中设置时只处理其中的 handler 的失败，没有设置的 scope 使用外层的
+    pub fn on_error<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&DispatchError, &Request) -> Response + 'static,
+    {
+        self.error_handler = Some(Box::new(f));
+        self
+    }
+    /// 每次分发后记录请求概要和各个 handler 的结果
+    pub fn logger(mut self, logger: Logger) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+    /// 依次交给每个匹配的 handler 处理，返回各自的结果
+    pub async fn dispatch(&self, req: Request) -> Vec<Result<Response, DispatchError>> {
+        self.dispatch_report(req).await.into_results()
+    }
+    /// 与 `dispatch` 相同，同时记录每个 handler 的耗时和被重试的失败
+    pub async fn dispatch_report(&self, mut req: Request) -> DispatchReport {
+        req.set_context(self.context.clone());
+        let dispatch_start = Instant::now();
+        let mut reports = vec![];
+        for entry in self.services.iter() {
+            let params = match entry.info.matches(&req) {
+                Some(params) => params,
+                None => continue,
+            };
+            let timeout = entry
+                .timeout
+                .or_else(|| entry.scoped(|scope| scope.default_timeout))
+                .or(self.default_timeout);
+            let panic_policy = entry
+                .scoped(|scope| scope.panic_policy)
+                .or(self.panic_policy)
+                .unwrap_or_default();
+            let (trace, start) = (Trace::new(timeout), Instant::now());
+            // 每个 handler 拿到自己的请求，可以取走其中的请求体
+            let handler_req = entry.request(&req, &params);
+            // 先取得所在 scope 和 `App` 上共享的名额
+            let scopes = entry.scopes.iter().flat_map(|scope| &scope.limiters);
+            let service = limit::SharedLimits {
+                limiters: scopes.chain(&self.limiters).map(AsRef::as_ref).collect(),
+                inner: entry.service.as_ref(),
+            };
+            let result = self.call(&service, panic_policy, handler_req, &trace).await;
+            let outcome = Outcome::of(&result);
+            let error_handler = entry
+                .scoped(|scope| scope.error_handler.as_ref())
+                .or(self.error_handler.as_ref());
+            // 保留被转换为响应的错误，报告和日志记录转换之前的结果
+            let (result, handled) = match (result, error_handler) {
+                (Err(err), Some(on_error)) => {
+                    (Ok(on_error(&err, &entry.request(&req, &params))), Some(err))
+                }
+                (result, _) => (result, None),
+            };
+            reports.push(HandlerReport::new(
+                entry.info.clone(),
+                outcome,
+                result,
+                handled,
+                trace,
+                start.elapsed(),
+            ));
+        }
+        let report = DispatchReport::new(reports, dispatch_start.elapsed());
+        if let Some(logger) = &self.logger {
+            logger.log(&req, &report);
+        }
+        report
+    }
+    /// 按 `PanicPolicy` 捕获提取和 handler 中的 panic，超时由 `ServiceWrapper` 按每次尝试处理
+    async fn call(
+        &self,
+        service: &dyn Service,
+        panic_policy: PanicPolicy,
+        req: Request,
+        trace: &Trace,
+    ) -> Result<Response, DispatchError> {
+        let start = Instant::now();
+        let future: ServiceFuture<'_> = match panic_policy {
+            PanicPolicy::Propagate => service.handle_request(req, trace),
+            PanicPolicy::Isolate => {
+                match std_panic::catch_unwind(AssertUnwindSafe(|| {
+                    service.handle_request(req, trace)
+                })) {
+                    Ok(future) => Box::pin(async move {
+                        CatchUnwind::new(future)
+                            .await
+                            .unwrap_or_else(|message| Err(DispatchError::Panic(message)))
+                    }),
+                    Err(payload) => {
+                        let message = panic::panic_message(payload);
+                        Box::pin(std::future::ready(Err(DispatchError::Panic(message))))
+                    }
+                }
+            }
+        };
+        let result = future.await;
+        // panic 的那次尝试没有执行完，由这里记录
+        if let Err(DispatchError::Panic(_)) = result {
+            trace.attempt(&result, start.elapsed());
+        }
+        result
+    }
+}
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 设置 T 为 Handler 接受的类型
+pub trait Handler<T, R>: Clone + 'static
+where
+    R: Future,
+{
+    fn call(&self, param: T) -> R;
+    /// 默认以类型名作为名字，`#[handler(...)]` 生成的 handler 会提供注册时的名字
+    fn info(&self) -> HandlerInfo {
+        HandlerInfo::new(type_name::<Self>())
+    }
+}
+#[rustfmt::skip]
+mod _impl_handler {
+    use super::*;
+    // delegate
+    impl<F, R> Handler<(), R> for F
+    where
+        F: Fn() -> R + Clone + 'static,
+        R: Future,
+    {
+        fn call(&self, param: ()) -> R {
+            (self)()
+        }
+    }
+    macro_rules! f {
+        (($($Ts:ident),*), ($($Ns:tt),*)) => {
+            impl<F, $($Ts,)* R> Handler<( $($Ts, )* ), R> for F
+            where
+                F: Fn( $($Ts,)* ) -> R + Clone + 'static,
+                R: Future,
+            {
+                fn call(&self, param: ( $($Ts,)* )) -> R {
+                    (self)(
+                        $(param.$Ns, )*
+                    )
+                }
+            }
+        };
+    }
+    all_tuples!(f);
+}
+
+type ServiceFuture<'a> = Pin<Box<dyn Future<Output = Result<Response, DispatchError>> + 'a>>;
+
+/// 中间件同样实现 `Service`，包装内部的 `Box<dyn Service>`
+///
+/// 请求按值传入，最终交给 handler 的参数提取，最后一个参数可以取走其中的请求体
+trait Service {
+    fn handle_request<'a>(&'a self, req: Request, trace: &'a Trace) -> ServiceFuture<'a>;
+}
+
+struct ServiceWrapper<F, T, R, M> {
+    f: F,
+    /// 通过 `App::reusable_handler` 注册时可以复制参数，供重试复用
+    clone_params: Option<fn(&T) -> T>,
+    _t: PhantomData<(T, R, M)>,
+}
+impl<F, T, R, M> ServiceWrapper<F, T, R, M>
+where
+    F: Handler<T, R>,
+    T: HandlerParams<M> + 'static,
+    R: Future,
+    R::Output: Responder,
+{
+    pub fn new(f: F) -> Self {
+        Self {
+            f,
+            clone_params: None,
+            _t: PhantomData,
+        }
+    }
+    pub fn reusing_params(f: F) -> Self
+    where
+        T: Clone,
+    {
+        Self {
+            clone_params: Some(T::clone),
+            ..Self::new(f)
+        }
+    }
+    /// 提取参数并调用 handler，`Trace` 中有上一次尝试留下的参数时直接使用
+    async fn attempt(&self, mut req: Request, trace: &Trace) -> Result<Response, DispatchError> {
+        let params = match trace.cached_params::<T>() {
+            Some(params) => params,
+            None => T::extract_params(&mut req)
+                .await
+                .map_err(DispatchError::Extract)?,
+        };
+        if let Some(clone) = self.clone_params {
+            trace.cache_params(|| clone(&params));
+        }
+        self.f
+            .call(params)
+            .await
+            .respond_to()
+            .map_err(DispatchError::Handler)
+    }
+}
+impl<F, T, R, M> Service for ServiceWrapper<F, T, R, M>
+where
+    F: Handler<T, R>,
+    T: HandlerParams<M> + 'static,
+    R: Future,
+    R::Output: Responder,
+{
+    fn handle_request<'a>(&'a self, mut req: Request, trace: &'a Trace) -> ServiceFuture<'a> {
+        Box::pin(async move {
+            let start = Instant::now();
+            let result = match trace.timeout() {
+                Some(duration) => {
+                    // 每次尝试有自己的令牌，超时只取消这一次
+                    let token = CancellationToken::new();
+                    req.extensions_mut().insert(token.clone());
+                    let attempt = Box::pin(self.attempt(req, trace));
+                    cancel::cancel_after(duration, &token, attempt)
+                        .await
+                        .unwrap_or(Err(DispatchError::Timeout(duration)))
+                }
+                None => self.attempt(req, trace).await,
+            };
+            trace.attempt(&result, start.elapsed());
+            result
+        })
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_add_handlers() {
+    use mockall::*;
+
+    #[automock]
+    pub trait Calls {
+        fn none();
+        fn one(s: String);
+        fn two(n1: u32, n2: u64);
+        fn signed(n: i64, f: f64);
+        fn parsed(ip: std::net::IpAddr);
+        fn optional(n: Option<u32>, r: Option<f64>);
+        fn sixteen(n1: u8, n14: char, n15: Option<bool>, s: String);
+    }
+
+    async fn none() {
+        MockCalls::none();
+    }
+
+    async fn one(s: String) {
+        MockCalls::one(s);
+    }
+
+    async fn two(n1: u32, n2: u64) {
+        MockCalls::two(n1, n2);
+    }
+
+    async fn signed(n: i64, f: f64) {
+        MockCalls::signed(n, f);
+    }
+
+    async fn parsed(Parsed(ip): Parsed<std::net::IpAddr>) {
+        MockCalls::parsed(ip);
+    }
+
+    async fn optional(n: Option<u32>, r: Result<f64, ExtractError>) {
+        MockCalls::optional(n, r.ok());
+    }
+
+    #[rustfmt::skip]
+    #[allow(clippy::too_many_arguments)]
+    async fn sixteen(
+        n1: u8, n2: u16, n3: u32, n4: u64, n5: u128, n6: usize, n7: i16, n8: i32,
+        n9: i64, n10: i128, n11: isize, n12: f32, n13: f64, n14: char, n15: Option<bool>, s: String,
+    ) {
+        MockCalls::sixteen(n1, n14, n15, s);
+    }
+
+    // 每个请求都会交给所有 handler，提取失败的 handler 不会被调用
+    let none_ctx = MockCalls::none_context();
+    none_ctx.expect().times(4).returning(|| {});
+    let one_ctx = MockCalls::one_context();
+    one_ctx.expect().times(4).returning(|_| {});
+    let two_ctx = MockCalls::two_context();
+    two_ctx
+        .expect()
+        .withf(|n1, n2| (*n1, *n2) == (3333, 3333) || (*n1, *n2) == (1, 1))
+        .times(2)
+        .returning(|_, _| {});
+    let signed_ctx = MockCalls::signed_context();
+    signed_ctx
+        .expect()
+        .withf(|n, f| *n as f64 == *f)
+        .times(2)
+        .returning(|_, _| {});
+    let parsed_ctx = MockCalls::parsed_context();
+    parsed_ctx
+        .expect()
+        .withf(|ip| ip.is_loopback())
+        .times(1)
+        .returning(|_| {});
+    let optional_ctx = MockCalls::optional_context();
+    for (n, r) in [
+        (Some(3333), Some(3333.0)),
+        (None, Some(-3.5)),
+        (None, None),
+        (Some(1), Some(1.0)),
+    ] {
+        optional_ctx
+            .expect()
+            .withf(move |n2, r2| (*n2, *r2) == (n, r))
+            .times(1)
+            .returning(|_, _| {});
+    }
+
+    // 只有 "1" 能被 sixteen 的每个参数接受，bool 解析失败时为 None
+    let sixteen_ctx = MockCalls::sixteen_context();
+    sixteen_ctx
+        .expect()
+        .withf(|n1, n14, n15, s| (*n1, *n14, *n15, s.as_str()) == (1, '1', None, "1"))
+        .times(1)
+        .returning(|_, _, _, _| {});
+
+    let app = App::new()
+        .handler(none)
+        .handler(one)
+        .handler(two)
+        .handler(signed)
+        .handler(parsed)
+        .handler(optional)
+        .handler(sixteen);
+    app.dispatch(Request::new("3333")).await;
+    app.dispatch(Request::new("-3.5")).await;
+    app.dispatch(Request::new("127.0.0.1")).await;
+    app.dispatch(Request::new("1")).await;
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_derive_from_request() {
+    #[derive(Debug, FromRequest)]
+    struct ListUsers {
+        #[from_request(path = "org")]
+        org: String,
+        #[from_request(query, default)]
+        limit: u32,
+        #[from_request(query = "q", default = "default_keyword")]
+        keyword: String,
+        #[from_request(header = "X-Request-Id")]
+        request_id: u64,
+        #[from_request(body)]
+        body: i32,
+        // 没有属性的字段通过自身的 FromRequest 提取
+        raw: Option<u8>,
+    }
+    fn default_keyword() -> String {
+        "*".to_string()
+    }
+
+    #[derive(FromRequest)]
+    struct Pair(
+        #[from_request(query = "a")] u32,
+        #[from_request(query = "b")] u32,
+    );
+
+    async fn list_users(users: ListUsers, Pair(a, b): Pair) {
+        assert_eq!(users.org, "rust");
+        assert_eq!(users.limit, 0);
+        assert_eq!(users.keyword, "*");
+        assert_eq!(users.request_id, 42);
+        assert_eq!(users.body, -1);
+        assert_eq!(users.raw, None);
+        assert_eq!((a, b), (1, 2));
+    }
+
+    let req = Request::new("-1")
+        .with_uri("/orgs/rust/users?a=1&b=2")
+        .with_param("org", "rust")
+        .with_header("x-request-id", "42");
+    let users = ListUsers::from_request(&req).unwrap();
+    assert_eq!(users.request_id, 42);
+    App::new().handler(list_users).dispatch(req).await;
+
+    // 缺少必需的请求头
+    let req = Request::new("-1").with_param("org", "rust");
+    let err = ListUsers::from_request(&req).unwrap_err();
+    assert!(matches!(
+        err,
+        ExtractError::Missing { source: Source::Header, ref name } if name == "X-Request-Id"
+    ));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_handler_attribute() {
+    use mockall::*;
+
+    #[automock]
+    pub trait Calls {
+        fn create(name: String);
+        fn get(id: u32, user: u32);
+        fn anonymous();
+    }
+
+    #[handler(name = "create_user", route = "/users", method = "POST")]
+    async fn create_user(name: String) {
+        MockCalls::create(name);
+    }
+
+    #[derive(FromRequest)]
+    struct UserId(#[from_request(path = "id")] u32);
+
+    // 路径参数由路由捕获
+    #[handler(route = "/users/:id", method = "GET")]
+    async fn get_user(Parsed(id): Parsed<u32>, user: UserId) {
+        MockCalls::get(id, user.0);
+    }
+
+    async fn anonymous() {
+        MockCalls::anonymous();
+    }
+
+    let create_ctx = MockCalls::create_context();
+    create_ctx
+        .expect()
+        .withf(|name| name == "alice")
+        .times(1)
+        .returning(|_| {});
+    let get_ctx = MockCalls::get_context();
+    get_ctx
+        .expect()
+        .withf(|id, user| (*id, *user) == (7, 42))
+        .times(2)
+        .returning(|_, _| {});
+    let anonymous_ctx = MockCalls::anonymous_context();
+    anonymous_ctx.expect().times(2).returning(|| {});
+
+    // 函数本身不受影响，可以直接调用
+    get_user(Parsed(7), UserId(42)).await;
+
+    let app = App::new()
+        .handler(CreateUserHandler)
+        .handler(GetUserHandler)
+        .handler(anonymous);
+    let names: Vec<_> = app.handlers().map(HandlerInfo::name).collect();
+    assert_eq!(names[..2], ["create_user", "get_user"]);
+    assert!(names[2].ends_with("anonymous"));
+    assert_eq!(app.handlers().nth(1).unwrap().route(), Some("/users/:id"));
+
+    app.dispatch(Request::new("alice").with_method("POST").with_uri("/users"))
+        .await;
+    app.dispatch(Request::new("7").with_uri("/users/42")).await;
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_responder() {
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Deserialize, Serialize)]
+    struct User {
+        name: String,
+    }
+
+    #[handler(route = "/users", method = "POST")]
+    async fn create_user(Json(user): Json<User>) -> (u16, Json<User>) {
+        (201, Json(user))
+    }
+
+    #[handler(route = "/users/:id")]
+    async fn get_user(id: Option<UserId>) -> Result<String, ExtractError> {
+        Ok(format!(
+            "user {}",
+            id.ok_or_else(|| ExtractError::parse::<u32>("no id"))?.0
+        ))
+    }
+
+    #[derive(FromRequest)]
+    struct UserId(#[from_request(path = "id")] u32);
+
+    let app = App::new()
+        .handler(CreateUserHandler)
+        .handler(GetUserHandler)
+        .extractor_config(JsonConfig {
+            limit: 32,
+            content_type_required: false,
+        });
+
+    let results = app
+        .dispatch(
+            Request::new(r#"{"name":"alice"}"#)
+                .with_method("POST")
+                .with_uri("/users"),
+        )
+        .await;
+    let response = results[0].as_ref().unwrap();
+    assert_eq!(response.status(), 201);
+    assert_eq!(response.body(), r#"{"name":"alice"}"#);
+
+    // 请求体超过 App 上配置的限制
+    let results = app
+        .dispatch(
+            Request::new(r#"{"name":"a very very very long name"}"#)
+                .with_method("POST")
+                .with_uri("/users"),
+        )
+        .await;
+    assert!(matches!(
+        results[0],
+        Err(DispatchError::Extract(ExtractError::PayloadTooLarge { .. }))
+    ));
+
+    let results = app.dispatch(Request::new("").with_uri("/users/42")).await;
+    assert_eq!(results[0].as_ref().unwrap().body(), "user 42");
+    let results = app.dispatch(Request::new("").with_uri("/users/x")).await;
+    assert!(matches!(results[0], Err(DispatchError::Handler(_))));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_form_and_query() {
+    use serde::Deserialize;
+
+    #[derive(Deserialize)]
+    struct Login {
+        user: String,
+        password: String,
+    }
+    #[derive(Deserialize)]
+    struct Redirect {
+        next: Option<String>,
+    }
+
+    // 表单和查询字符串可以在同一个 handler 中组合使用，取走请求体的表单放在最后
+    async fn login(Query(redirect): Query<Redirect>, Form(login): Form<Login>) -> String {
+        format!(
+            "{}:{} -> {}",
+            login.user,
+            login.password,
+            redirect.next.unwrap_or_default()
+        )
+    }
+
+    let app = App::new().handler(login);
+    let req = Request::new("user=alice&password=p%40ss+word")
+        .with_method("POST")
+        .with_uri("/login?next=%2Fhome")
+        .with_header("Content-Type", "application/x-www-form-urlencoded");
+    let results = app.dispatch(req).await;
+    assert_eq!(
+        results[0].as_ref().unwrap().body(),
+        "alice:p@ss word -> /home"
+    );
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_async_extractor() {
+    use std::collections::HashMap;
+
+    struct UserStore(HashMap<u32, String>);
+    impl UserStore {
+        async fn load(&self, id: u32) -> Option<String> {
+            tokio::task::yield_now().await;
+            self.0.get(&id).cloned()
+        }
+    }
+
+    // 需要等待的提取器只实现 FromRequestParts
+    struct CurrentUser(String);
+    impl FromRequestParts for CurrentUser {
+        fn from_request_parts(req: &Request) -> ExtractFuture<'_, Self> {
+            Box::pin(async move {
+                let id = Source::Header.extract::<u32>(req, "X-User-Id")?;
+                let store = req.extensions().get::<UserStore>().unwrap();
+                match store.load(id).await {
+                    Some(name) => Ok(CurrentUser(name)),
+                    None => Err(ExtractError::Missing {
+                        source: Source::Header,
+                        name: "X-User-Id".to_string(),
+                    }),
+                }
+            })
+        }
+    }
+
+    // 同步的提取器不需要任何改动就能与之组合
+    async fn greet(CurrentUser(name): CurrentUser, greeting: String) -> String {
+        format!("{}, {}", greeting, name)
+    }
+
+    let store = UserStore(vec![(1, "alice".to_string())].into_iter().collect());
+    let app = App::new().handler(greet);
+    let req = Request::new("hello").with_extension(store);
+    let results = app
+        .dispatch(req.clone().with_header("X-User-Id", "1"))
+        .await;
+    assert_eq!(results[0].as_ref().unwrap().body(), "hello, alice");
+
+    let results = app.dispatch(req.with_header("X-User-Id", "2")).await;
+    assert!(matches!(
+        results[0],
+        Err(DispatchError::Extract(ExtractError::Missing { .. }))
+    ));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_body_extractor() {
+    // 共享的提取器在前，取走请求体的 Body 在最后
+    async fn upload(name: Header<ContentLength>, body: Body) -> String {
+        let sizes: Vec<_> = body.chunks(4).map(|chunk| chunk.len()).collect();
+        format!("{} {:?}", name.0 .0, sizes)
+    }
+    async fn echo(body: Body) -> Result<String, std::str::Utf8Error> {
+        body.into_string()
+    }
+    async fn raw(n: u64, bytes: Vec<u8>) -> String {
+        format!("{} {}", n, bytes.len())
+    }
+    // 组合子包装的提取器同样取走请求体
+    async fn json(n: Option<Json<u64>>) -> String {
+        format!("{:?}", n.map(Json::into_inner))
+    }
+
+    let app = App::new()
+        .handler(upload)
+        .handler(echo)
+        .handler(raw)
+        .handler(json)
+        .extractor_config(JsonConfig {
+            content_type_required: false,
+            ..Default::default()
+        });
+    let req = Request::new("1234567890").with_header("Content-Length", "10");
+    let results = app.dispatch(req).await;
+    assert_eq!(results[0].as_ref().unwrap().body(), "10 [4, 4, 2]");
+    // 每个 handler 拿到各自的请求，前一个 handler 取走请求体不影响后面的
+    assert_eq!(results[1].as_ref().unwrap().body(), "1234567890");
+    assert_eq!(results[2].as_ref().unwrap().body(), "1234567890 10");
+    assert_eq!(results[3].as_ref().unwrap().body(), "Some(1234567890)");
+
+    // 前面的参数读到请求体后，最后的参数仍然只能取走一次
+    let mut req = Request::new("42");
+    let _ = <(u64, Body)>::extract_params(&mut req).await.unwrap();
+    assert!(req.body().is_empty());
+    assert!(Body::from_request_body(&mut req).await.unwrap().is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_extractor_config() {
+    async fn hex(n: u32) -> String {
+        n.to_string()
+    }
+    async fn trimmed(s: String) -> String {
+        format!("[{}]", s)
+    }
+    async fn ids(Split(ids): Split<String>) -> String {
+        ids.join("+")
+    }
+
+    let app = App::new()
+        .handler(hex)
+        .handler(trimmed)
+        .handler(ids)
+        .extractor_config(IntegerConfig { radix: 16 })
+        .extractor_config(StringConfig { trim: true })
+        .extractor_config(ListConfig { separator: ';' });
+    let results = app.dispatch(Request::new(" ff;a ")).await;
+    // 十六进制不允许空白，hex 提取失败
+    assert!(results[0].is_err());
+    assert_eq!(results[1].as_ref().unwrap().body(), "[ff;a]");
+    assert_eq!(results[2].as_ref().unwrap().body(), "ff+a");
+
+    // 配置在分发时通过请求交给提取器，请求自身的扩展优先
+    let req = Request::new("ff").with_extension(IntegerConfig { radix: 10 });
+    let results = app.dispatch(req).await;
+    assert!(results[0].is_err());
+    let results = app.dispatch(Request::new("ff")).await;
+    assert_eq!(results[0].as_ref().unwrap().body(), "255");
+    assert!(Request::new("").app_context().get::<ListConfig>().is_none());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_scope() {
+    #[handler(route = "/items")]
+    async fn number(n: u32) -> String {
+        n.to_string()
+    }
+    async fn any(n: u32) -> String {
+        format!("any {}", n)
+    }
+
+    let app = App::new()
+        .handler(NumberHandler)
+        .scope("/hex", |scope| {
+            scope
+                .handler(NumberHandler)
+                .scope("/:version", |scope| {
+                    scope.handler(NumberHandler).handler(any)
+                })
+                .extractor_config(IntegerConfig { radix: 16 })
+        })
+        .scope("/oct", |scope| {
+            scope
+                .handler(NumberHandler)
+                .extractor_config(IntegerConfig { radix: 8 })
+        })
+        .extractor_config(IntegerConfig { radix: 10 });
+    let prefixes: Vec<_> = app.handlers().map(HandlerInfo::prefix).collect();
+    assert_eq!(
+        prefixes,
+        ["", "/hex", "/hex/:version", "/hex/:version", "/oct"]
+    );
+
+    let bodies = |uri: &str| {
+        let results = app.dispatch(Request::new("17").with_uri(uri));
+        async {
+            let results = results.await.into_iter();
+            results
+                .map(|r| r.unwrap().body().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+    // scope 的配置只作用于其中的 handler，内层没有注册的配置沿用外层
+    assert_eq!(bodies("/items").await, ["17"]);
+    // `any` 没有路由，`/hex/:version` 之下的请求都交给它
+    assert_eq!(bodies("/hex/items").await, ["23", "any 23"]);
+    assert_eq!(bodies("/hex/v2/items").await, ["23", "any 23"]);
+    assert_eq!(bodies("/hex/v2/other").await, ["any 23"]);
+    assert_eq!(bodies("/oct/items").await, ["15"]);
+    assert!(bodies("/other/items").await.is_empty());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_panic_isolation() {
+    struct Exploding;
+    impl FromRequest for Exploding {
+        type Error = ExtractError;
+        fn from_request(req: &Request) -> Result<Self, Self::Error> {
+            panic!("extractor exploded")
+        }
+    }
+    async fn extract(_: Exploding) {}
+    async fn divide(n: u32) -> String {
+        tokio::task::yield_now().await;
+        (100 / n).to_string()
+    }
+    async fn echo(s: String) -> String {
+        s
+    }
+
+    // 提取和 handler 中的 panic 都只影响各自的 handler
+    let app = App::new().handler(extract).handler(divide).handler(echo);
+    let results = app.dispatch(Request::new("0")).await;
+    assert!(matches!(&results[0], Err(DispatchError::Panic(m)) if m == "extractor exploded"));
+    assert!(matches!(&results[1], Err(DispatchError::Panic(m)) if m.contains("divide by zero")));
+    assert_eq!(results[2].as_ref().unwrap().body(), "0");
+
+    let app = App::new()
+        .handler(divide)
+        .panic_policy(PanicPolicy::Propagate);
+    let result = CatchUnwind::new(Box::pin(app.dispatch(Request::new("0")))).await;
+    assert!(result.unwrap_err().contains("divide by zero"));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_timeout() {
+    use std::sync::Mutex;
+
+    async fn quick(s: String) -> String {
+        sleep(Duration::from_millis(5)).await;
+        s
+    }
+    // 记下提取到的令牌，以便在超时后检查
+    let captured = Arc::new(Mutex::new(None));
+    let slot = captured.clone();
+    let hang = move |token: CancellationToken| {
+        *slot.lock().unwrap() = Some(token);
+        std::future::pending::<()>()
+    };
+
+    let app = App::new()
+        .handler(hang)
+        .timeout(Duration::from_millis(20))
+        .handler(quick)
+        .default_timeout(Duration::from_secs(5));
+    let results = app.dispatch(Request::new("done")).await;
+    assert!(matches!(results[0], Err(DispatchError::Timeout(d)) if d.as_millis() == 20));
+    assert_eq!(results[1].as_ref().unwrap().body(), "done");
+
+    let token = captured.lock().unwrap().take().unwrap();
+    assert!(token.is_cancelled());
+
+    // 令牌在丢弃 handler 之前取消，handler 可以等待 `cancelled` 后收尾
+    let cleaned_up = Arc::new(Mutex::new(false));
+    let flag = cleaned_up.clone();
+    let graceful = move |token: CancellationToken| {
+        let flag = flag.clone();
+        async move {
+            token.cancelled().await;
+            *flag.lock().unwrap() = true;
+        }
+    };
+    let app = App::new()
+        .handler(graceful)
+        .timeout(Duration::from_millis(10));
+    let results = app.dispatch(Request::new("")).await;
+    assert!(matches!(results[0], Err(DispatchError::Timeout(_))));
+    assert!(*cleaned_up.lock().unwrap());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_on_error() {
+    use std::num::ParseIntError;
+
+    async fn id(n: u32) -> String {
+        n.to_string()
+    }
+    async fn parse(s: String) -> Result<String, ParseIntError> {
+        s.parse::<i8>().map(|n| n.to_string())
+    }
+    async fn hang() {
+        std::future::pending::<()>().await
+    }
+
+    let app = App::new()
+        .handler(id)
+        .handler(parse)
+        .handler(hang)
+        .timeout(Duration::from_millis(10))
+        .on_error(|err: &DispatchError, req: &Request| {
+            if let Some(e) = err.downcast_ref::<ExtractError>() {
+                Response::new(400).with_body(e.to_string())
+            } else if err.is_timeout() {
+                Response::new(504)
+            } else if err.downcast_ref::<ParseIntError>().is_some() {
+                Response::new(422).with_body(req.body_str().unwrap().to_string())
+            } else {
+                Response::new(500)
+            }
+        });
+    let results = app.dispatch(Request::new("300")).await;
+    let statuses: Vec<_> = results
+        .iter()
+        .map(|r| r.as_ref().unwrap().status())
+        .collect();
+    assert_eq!(statuses, [200, 422, 504]);
+
+    let results = app.dispatch(Request::new("x")).await;
+    let response = results[0].as_ref().unwrap();
+    assert_eq!(response.status(), 400);
+    assert!(response.body().contains("u32"));
+
+    // 错误处理器拿到的请求带有路由捕获的参数
+    #[handler(route = "/items/:id")]
+    async fn item(n: u32) {}
+    let app = App::new()
+        .handler(ItemHandler)
+        .on_error(|_: &DispatchError, req: &Request| {
+            Response::new(400).with_body(req.param("id").unwrap_or_default().to_string())
+        });
+    let results = app.dispatch(Request::new("x").with_uri("/items/7")).await;
+    assert_eq!(results[0].as_ref().unwrap().body(), "7");
+
+    // scope 中的错误处理器优先，没有设置的 scope 使用外层的
+    let app = App::new()
+        .scope("/api", |scope| {
+            scope
+                .handler(ItemHandler)
+                .on_error(|_: &DispatchError, _: &Request| Response::new(422))
+        })
+        .scope("/admin", |scope| scope.handler(ItemHandler))
+        .on_error(|_: &DispatchError, _: &Request| Response::new(400));
+    for (uri, status) in [("/api/items/7", 422), ("/admin/items/7", 400)] {
+        let results = app.dispatch(Request::new("x").with_uri(uri)).await;
+        assert_eq!(results[0].as_ref().unwrap().status(), status);
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_retry() {
+    use std::{
+        io,
+        sync::atomic::{AtomicU32, Ordering},
+    };
+
+    // 前两次调用失败
+    let calls = Arc::new(AtomicU32::new(0));
+    let counter = calls.clone();
+    let flaky = move || {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            match n {
+                0 | 1 => Err(io::Error::other("busy")),
+                _ => Ok("ready"),
+            }
+        }
+    };
+    async fn invalid() -> Result<(), std::fmt::Error> {
+        Err(std::fmt::Error)
+    }
+
+    let policy = RetryPolicy::new(3).backoff(Duration::from_millis(1), Duration::from_millis(5));
+    let app = App::new()
+        .handler(flaky)
+        .retry(policy.clone().retry_on::<io::Error>())
+        .handler(invalid)
+        .retry(policy.clone().retry_on::<io::Error>());
+    let report = app.dispatch_report(Request::new("")).await;
+    let handlers = report.handlers();
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    assert_eq!(handlers[0].result().as_ref().unwrap().body(), "ready");
+    // 每次尝试都出现在报告中，包括最后成功的那一次
+    let attempts = handlers[0].attempts();
+    assert_eq!(attempts.len(), 3);
+    assert!(attempts[1].error().unwrap().contains("busy"));
+    assert!(attempts[1].delay().is_some());
+    assert_eq!(attempts[2].outcome(), Outcome::Responded);
+    assert_eq!(attempts[2].delay(), None);
+    // 不满足条件的失败不重试
+    assert!(handlers[1].result().is_err());
+    assert_eq!(handlers[1].attempts().len(), 1);
+
+    // 复用参数时只在第一次尝试中提取
+    #[derive(Clone)]
+    struct Counted;
+    impl FromRequest for Counted {
+        type Error = ExtractError;
+        fn from_request(req: &Request) -> Result<Self, Self::Error> {
+            let counter = req.extensions().get::<Arc<AtomicU32>>().unwrap();
+            counter.fetch_add(1, Ordering::SeqCst);
+            Ok(Counted)
+        }
+    }
+    let calls = Arc::new(AtomicU32::new(0));
+    let counter = calls.clone();
+    let counted = move |_: Counted| {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            match n {
+                0 | 1 => Err(io::Error::other("busy")),
+                _ => Ok("ready"),
+            }
+        }
+    };
+    let app = App::new()
+        .reusable_handler(counted)
+        .retry(policy.clone().retry_on::<io::Error>().reuse_params(true));
+    let extractions = Arc::new(AtomicU32::new(0));
+    let req = Request::new("").with_extension(extractions.clone());
+    let results = app.dispatch(req).await;
+    assert_eq!(results[0].as_ref().unwrap().body(), "ready");
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+    assert_eq!(extractions.load(Ordering::SeqCst), 1);
+
+    // 超时按每次尝试计算，超时的那一次同样出现在报告中
+    let calls = Arc::new(AtomicU32::new(0));
+    let counter = calls.clone();
+    let slow_once = move || {
+        let n = counter.fetch_add(1, Ordering::SeqCst);
+        async move {
+            if n == 0 {
+                std::future::pending::<()>().await;
+            }
+            "ready"
+        }
+    };
+    let app = App::new()
+        .handler(slow_once)
+        .timeout(Duration::from_millis(10))
+        .retry(policy.retry_if(DispatchError::is_timeout));
+    let report = app.dispatch_report(Request::new("")).await;
+    let handler = &report.handlers()[0];
+    assert_eq!(handler.result().as_ref().unwrap().body(), "ready");
+    let outcomes: Vec<_> = handler.attempts().iter().map(Attempt::outcome).collect();
+    assert_eq!(outcomes, [Outcome::TimedOut, Outcome::Responded]);
+    assert!(handler.attempts()[0].delay().is_some());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_circuit_breaker() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    let healthy = Arc::new(AtomicBool::new(false));
+    let flag = healthy.clone();
+    let backend = move |_: u32| {
+        let healthy = flag.load(Ordering::SeqCst);
+        async move {
+            if healthy {
+                Ok("up")
+            } else {
+                Err("down")
+            }
+        }
+    };
+    let breaker = CircuitBreaker::new()
+        .window(4, 2)
+        .cool_down(Duration::from_millis(20));
+    let app = App::new()
+        .handler(backend.clone())
+        .circuit_breaker(breaker.clone());
+    let state = |app: &App| app.circuits().next().unwrap().1;
+
+    // 提取失败不计入失败率
+    let bad = || Request::new("x");
+    for _ in 0..4 {
+        assert!(matches!(
+            app.dispatch(bad()).await[0],
+            Err(DispatchError::Extract(_))
+        ));
+    }
+    assert_eq!(state(&app), CircuitState::Closed);
+
+    for _ in 0..2 {
+        assert!(matches!(
+            app.dispatch(Request::new("1")).await[0],
+            Err(DispatchError::Handler(_))
+        ));
+    }
+    assert_eq!(state(&app), CircuitState::Open);
+    assert!(matches!(
+        app.dispatch(Request::new("1")).await[0],
+        Err(DispatchError::CircuitOpen)
+    ));
+
+    // 冷却后试探失败，重新打开
+    sleep(Duration::from_millis(25)).await;
+    assert_eq!(state(&app), CircuitState::HalfOpen);
+    assert!(app.dispatch(Request::new("1")).await[0].is_err());
+    assert_eq!(state(&app), CircuitState::Open);
+
+    // 试探时的错误请求只归还名额，不改变状态
+    healthy.store(true, Ordering::SeqCst);
+    sleep(Duration::from_millis(25)).await;
+    assert!(app.dispatch(bad()).await[0].is_err());
+    assert_eq!(state(&app), CircuitState::HalfOpen);
+    assert!(app.dispatch(Request::new("1")).await[0].is_ok());
+    assert_eq!(state(&app), CircuitState::Closed);
+
+    // 内层限流的拒绝同样不计入失败率
+    let app = App::new()
+        .handler(backend)
+        .rate_limit(RateLimit::new(1, Duration::from_secs(60)))
+        .circuit_breaker(breaker);
+    assert!(app.dispatch(Request::new("1")).await[0].is_ok());
+    for _ in 0..3 {
+        assert!(matches!(
+            app.dispatch(Request::new("1")).await[0],
+            Err(DispatchError::RateLimited)
+        ));
+    }
+    assert_eq!(state(&app), CircuitState::Closed);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_limits() {
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    struct ClientId(String);
+    impl FromRequest for ClientId {
+        type Error = ExtractError;
+        fn from_request(req: &Request) -> Result<Self, Self::Error> {
+            Source::Header.extract(req, "X-Client").map(Self)
+        }
+    }
+    async fn ping() -> &'static str {
+        "pong"
+    }
+    async fn slow() {
+        sleep(Duration::from_millis(20)).await
+    }
+
+    let app = App::new()
+        .handler(ping)
+        .rate_limit(RateLimit::new(2, Duration::from_secs(60)).key_by::<ClientId>());
+    let alice = Request::new("").with_header("X-Client", "alice");
+    for _ in 0..2 {
+        assert!(app.dispatch(alice.clone()).await[0].is_ok());
+    }
+    let err = app.dispatch(alice).await.remove(0).unwrap_err();
+    assert!(err.is_rejected());
+    assert_eq!(err.to_string(), "rate limit exceeded");
+    let bob = Request::new("").with_header("X-Client", "bob");
+    assert!(app.dispatch(bob).await[0].is_ok());
+
+    let app = App::new()
+        .handler(slow)
+        .concurrency_limit(ConcurrencyLimit::new(1));
+    let (first, second) = tokio::join!(
+        app.dispatch(Request::new("")),
+        app.dispatch(Request::new(""))
+    );
+    assert!(first[0].is_ok());
+    assert!(matches!(second[0], Err(DispatchError::ConcurrencyLimited)));
+    // 名额在调用结束后归还
+    assert!(app.dispatch(Request::new("")).await[0].is_ok());
+
+    // scope 中的 handler 共享限流，键也可以异步提取
+    #[derive(Clone, PartialEq, Eq, Hash)]
+    struct User(String);
+    impl FromRequestParts for User {
+        fn from_request_parts(req: &Request) -> ExtractFuture<'_, Self> {
+            Box::pin(async move {
+                tokio::task::yield_now().await;
+                Source::Header.extract(req, "X-User").map(Self)
+            })
+        }
+    }
+    let limit = RateLimit::new(3, Duration::from_secs(60)).key_by::<User>();
+    let app = App::new()
+        .scope("/api", |scope| {
+            scope.handler(ping).handler(ping).shared_rate_limit(limit)
+        })
+        .handler(ping);
+    let alice = Request::new("")
+        .with_uri("/api/ping")
+        .with_header("X-User", "alice");
+    assert!(app.dispatch(alice.clone()).await.iter().all(Result::is_ok));
+    let results = app.dispatch(alice).await;
+    assert!(results[0].is_ok());
+    assert!(matches!(results[1], Err(DispatchError::RateLimited)));
+    assert!(results[2].is_ok());
+    let bob = Request::new("")
+        .with_uri("/api/ping")
+        .with_header("X-User", "bob");
+    assert!(app.dispatch(bob).await[1].is_ok());
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_dispatch_queue() {
+    use std::task::{Context, Poll, Waker};
+
+    async fn echo(s: String) -> String {
+        if s == "slow" {
+            sleep(Duration::from_millis(20)).await;
+        }
+        s
+    }
+    let app = App::new().handler(echo);
+    let body = |result: Result<Vec<Result<Response, DispatchError>>, QueueError>| {
+        result.map(|results| results[0].as_ref().unwrap().body().to_string())
+    };
+
+    // 同时只分发一个，第二个排队，第三个挤掉它
+    let queue = DispatchQueue::new(1, Overflow::DropOldest);
+    let (slow, a, b) = tokio::join!(
+        queue.dispatch(&app, Request::new("slow")),
+        queue.dispatch(&app, Request::new("a")),
+        queue.dispatch(&app, Request::new("b"))
+    );
+    assert_eq!(body(slow).unwrap(), "slow");
+    assert_eq!(body(a), Err(QueueError::Dropped));
+    assert_eq!(body(b).unwrap(), "b");
+    let metrics = queue.metrics();
+    assert_eq!(
+        (metrics.max_depth, metrics.dropped, metrics.completed),
+        (1, 1, 2)
+    );
+
+    let queue = DispatchQueue::new(1, Overflow::RejectNewest).concurrency(2);
+    let results = tokio::join!(
+        queue.dispatch(&app, Request::new("slow")),
+        queue.dispatch(&app, Request::new("slow")),
+        queue.dispatch(&app, Request::new("a")),
+        queue.dispatch(&app, Request::new("b"))
+    );
+    assert_eq!(body(results.2).unwrap(), "a");
+    assert_eq!(body(results.3), Err(QueueError::Full));
+
+    let queue = DispatchQueue::new(1, Overflow::Block);
+    let results = tokio::join!(
+        queue.dispatch(&app, Request::new("slow")),
+        queue.dispatch(&app, Request::new("a")),
+        queue.dispatch(&app, Request::new("b"))
+    );
+    assert_eq!(body(results.2).unwrap(), "b");
+    assert_eq!(queue.metrics().completed, 3);
+
+    // 放弃等待时移除排队位置，放弃分发时归还名额
+    let mut cx = Context::from_waker(Waker::noop());
+    let mut running = Box::pin(queue.dispatch(&app, Request::new("slow")));
+    assert!(running.as_mut().poll(&mut cx).is_pending());
+    let mut waiting = Box::pin(queue.dispatch(&app, Request::new("a")));
+    assert!(waiting.as_mut().poll(&mut cx).is_pending());
+    assert_eq!(queue.metrics().depth, 1);
+    drop(waiting);
+    assert_eq!(queue.metrics().depth, 0);
+    drop(running);
+    assert_eq!(queue.metrics().in_flight, 0);
+
+    // 同步调用者在各自的线程上用自己的 `App` 分发，共享同一个队列
+    let queue = DispatchQueue::new(2, Overflow::Block);
+    std::thread::scope(|s| {
+        let workers: Vec<_> = (0..3)
+            .map(|_| {
+                s.spawn(|| {
+                    let app = App::new().handler(echo);
+                    body(queue.dispatch_blocking(&app, Request::new("slow")))
+                })
+            })
+            .collect();
+        for worker in workers {
+            assert_eq!(worker.join().unwrap().unwrap(), "slow");
+        }
+    });
+    assert_eq!(queue.metrics().completed, 3);
+
+    queue.close();
+    let result = queue.dispatch(&app, Request::new("a")).await;
+    assert_eq!(body(result), Err(QueueError::Closed));
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_logger() {
+    use std::{cell::RefCell, rc::Rc};
+
+    async fn double(n: u32) -> String {
+        (n * 2).to_string()
+    }
+    let logs = Rc::new(RefCell::new(vec![]));
+    let sink = logs.clone();
+    let app = App::new()
+        .handler(double)
+        .handler(|| async { "hello" })
+        .logger(Logger::with_sink(LogFormat::Text, move |line| {
+            sink.borrow_mut().push(line.to_string())
+        }));
+    app.dispatch(Request::new("x").with_uri("/calc?v=1")).await;
+    let text = logs.borrow_mut().remove(0);
+    let lines: Vec<_> = text.lines().collect();
+    assert!(lines[0].starts_with("GET \"/calc?v=1\" body=1B handlers=2 "));
+    assert!(lines[1].contains("::double extract_failed error="));
+    assert!(lines[2].contains(" ok status=200 attempts=1 "));
+
+    // 记录 `on_error` 转换之前的错误，超时和被拒绝的尝试同样计数，客户端的换行被转义
+    async fn hang() {
+        std::future::pending::<()>().await
+    }
+    let sink = logs.clone();
+    let app = App::new()
+        .handler(double)
+        .handler(hang)
+        .timeout(Duration::from_millis(5))
+        .handler(double)
+        .rate_limit(RateLimit::new(1, Duration::from_secs(60)))
+        .on_error(|_: &DispatchError, _: &Request| Response::new(400))
+        .logger(Logger::with_sink(LogFormat::Text, move |line| {
+            sink.borrow_mut().push(line.to_string())
+        }));
+    app.dispatch(Request::new("1")).await;
+    let forged = Request::new("x").with_uri("/a\nGET /forged");
+    app.dispatch(forged).await;
+    let text = logs.borrow_mut().split_off(0).join("\n");
+    let lines: Vec<_> = text.lines().collect();
+    assert!(lines[2].contains(" timed_out error="));
+    assert!(lines[2].contains(" on_error=400 attempts=1 "));
+    assert!(lines[4].starts_with("GET \"/a\\nGET /forged\" "));
+    assert!(lines[5].contains(" extract_failed error=") && lines[5].contains(" on_error=400 "));
+    assert!(lines[7].contains(" rejected error=\"rate limit exceeded\" on_error=400 attempts=1 "));
+
+    let sink = logs.clone();
+    let app = App::new()
+        .handler(double)
+        .logger(Logger::with_sink(LogFormat::JsonLines, move |line| {
+            sink.borrow_mut().push(line.to_string())
+        }));
+    app.dispatch(Request::new("21")).await;
+    let record: serde_json::Value = serde_json::from_str(&logs.borrow()[0]).unwrap();
+    assert_eq!(record["path"], "/");
+    assert_eq!(record["handlers"][0]["outcome"], "ok");
+    assert_eq!(record["handlers"][0]["status"], 200);
+    assert!(record["handlers"][0]["on_error_status"].is_null());
+    assert!(record["handlers"][0]["name"]
+        .as_str()
+        .unwrap()
+        .ends_with("double"));
+}
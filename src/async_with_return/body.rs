@@ -0,0 +1,78 @@
+use std::{
+    future,
+    str::{self, Utf8Error},
+    sync::Arc,
+};
+
+use super::{ExtractFuture, FromRequestBody, Request};
+
+/// 请求体，作为 handler 的最后一个参数从请求中取走，每次调用只有一个提取器能拿到
+///
+/// `Json<T>`、`String` 等读取请求体的提取器同样会取走它，只能放在最后：
+///
+/// ```compile_fail
+/// use type_erase::async_with_return::{App, Body};
+///
+/// async fn upload(body: Body, name: String) {}
+/// App::new().handler(upload);
+/// ```
+#[derive(Debug)]
+pub struct Body(Arc<[u8]>);
+impl Body {
+    pub(super) fn new(bytes: Arc<[u8]>) -> Self {
+        Self(bytes)
+    }
+    /// 与 `self` 共享同一份数据，用于把请求体放回请求中重新提取
+    pub(super) fn share(&self) -> Arc<[u8]> {
+        self.0.clone()
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    pub fn bytes(&self) -> &[u8] {
+        &self.0
+    }
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+    pub fn into_string(self) -> Result<String, Utf8Error> {
+        str::from_utf8(&self.0).map(str::to_string)
+    }
+    /// 按 `size` 字节依次读取，最后一块可能不足 `size`
+    pub fn chunks(self, size: usize) -> Chunks {
+        assert!(size > 0, "chunk size must be positive");
+        Chunks {
+            body: self.0,
+            pos: 0,
+            size,
+        }
+    }
+}
+impl FromRequestBody for Body {
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        Box::pin(future::ready(Ok(req.take_body())))
+    }
+}
+
+/// 见 [`Body::chunks`]
+#[derive(Debug)]
+pub struct Chunks {
+    body: Arc<[u8]>,
+    pos: usize,
+    size: usize,
+}
+impl Iterator for Chunks {
+    type Item = Vec<u8>;
+    fn next(&mut self) -> Option<Vec<u8>> {
+        if self.pos >= self.body.len() {
+            return None;
+        }
+        let end = self.body.len().min(self.pos + self.size);
+        let chunk = self.body[self.pos..end].to_vec();
+        self.pos = end;
+        Some(chunk)
+    }
+}
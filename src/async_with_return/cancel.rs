@@ -0,0 +1,86 @@
+use std::{
+    convert::Infallible,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+    time::Duration,
+};
+
+use super::{timer, FromRequest, Request};
+
+/// handler 超时被放弃时取消，交给后台任务后可以据此提前退出
+///
+/// 超时后会先取消令牌，再轮询一次 handler 才丢弃它，handler 可以在 `cancelled` 返回后收尾。
+/// 没有设置超时的 handler 提取到的令牌永远不会被取消
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<Inner>);
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    /// 等待 `cancelled` 的任务
+    wakers: Mutex<Vec<Waker>>,
+}
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::Acquire)
+    }
+    /// 令牌被取消时完成
+    pub fn cancelled(&self) -> Cancelled<'_> {
+        Cancelled(self)
+    }
+    pub(super) fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::Release);
+        for waker in self.0.wakers.lock().unwrap().drain(..) {
+            waker.wake();
+        }
+    }
+}
+impl FromRequest for CancellationToken {
+    type Error = Infallible;
+    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+        Ok(req.extensions().get::<Self>().cloned().unwrap_or_default())
+    }
+}
+
+/// 见 [`CancellationToken::cancelled`]
+#[derive(Debug)]
+pub struct Cancelled<'a>(&'a CancellationToken);
+impl Future for Cancelled<'_> {
+    type Output = ();
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if self.0.is_cancelled() {
+            return Poll::Ready(());
+        }
+        let mut wakers = self.0 .0.wakers.lock().unwrap();
+        // 加锁后再检查一次，避免错过加锁前的 `cancel`
+        if self.0.is_cancelled() {
+            return Poll::Ready(());
+        }
+        if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+            wakers.push(cx.waker().clone());
+        }
+        Poll::Pending
+    }
+}
+
+/// 在 `duration` 内等待 `future`；超时后先取消 `token` 并再轮询一次，之后才丢弃它
+pub(super) async fn cancel_after<F: Future + Unpin>(
+    duration: Duration,
+    token: &CancellationToken,
+    mut future: F,
+) -> Option<F::Output> {
+    if let Some(output) = timer::timeout(duration, &mut future).await {
+        return Some(output);
+    }
+    // handler 可以在这次轮询中注意到取消并收尾
+    token.cancel();
+    std::future::poll_fn(|cx| Poll::Ready(Pin::new(&mut future).poll(cx))).await;
+    None
+}
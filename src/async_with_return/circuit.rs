@@ -0,0 +1,195 @@
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use super::{DispatchError, Request, Response, Service, ServiceFuture, Trace};
+
+/// 熔断器的状态，通过 `App::circuits` 查询
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// 正常调用，统计最近的失败率
+    Closed,
+    /// 直接返回 `DispatchError::CircuitOpen`，冷却结束后进入 `HalfOpen`
+    Open,
+    /// 放行一次试探调用，成功则关闭，失败则重新打开
+    HalfOpen,
+}
+
+/// handler 持续失败时暂停调用它，通过 `App::circuit_breaker` 设置给最近注册的 handler
+///
+/// 提取失败是请求的问题，内层限流或熔断的拒绝也没有调用 handler，都不计入失败率
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    failure_rate: f64,
+    window: usize,
+    min_calls: usize,
+    cool_down: Duration,
+}
+impl CircuitBreaker {
+    pub fn new() -> Self {
+        Self {
+            failure_rate: 0.5,
+            window: 10,
+            min_calls: 5,
+            cool_down: Duration::from_secs(5),
+        }
+    }
+    /// 最近的调用中失败的比例达到 `rate` 时打开，默认 0.5
+    pub fn failure_rate(mut self, rate: f64) -> Self {
+        assert!(0.0 < rate && rate <= 1.0, "failure rate must be in (0, 1]");
+        self.failure_rate = rate;
+        self
+    }
+    /// 统计最近 `window` 次调用，至少有 `min_calls` 次时才会打开，默认 10 和 5
+    pub fn window(mut self, window: usize, min_calls: usize) -> Self {
+        assert!(
+            min_calls > 0 && min_calls <= window,
+            "invalid circuit window"
+        );
+        self.window = window;
+        self.min_calls = min_calls;
+        self
+    }
+    /// 打开后等待多久再试探，默认 5 秒
+    pub fn cool_down(mut self, cool_down: Duration) -> Self {
+        self.cool_down = cool_down;
+        self
+    }
+}
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    state: CircuitState,
+    /// 最近的调用是否失败
+    outcomes: VecDeque<bool>,
+    opened_at: Instant,
+    probing: bool,
+}
+
+/// 熔断器的共享状态，`App` 持有一份用于查询
+#[derive(Debug)]
+pub(super) struct Circuit {
+    config: CircuitBreaker,
+    inner: Mutex<Inner>,
+}
+impl Circuit {
+    pub(super) fn new(config: CircuitBreaker) -> Self {
+        Self {
+            config,
+            inner: Mutex::new(Inner {
+                state: CircuitState::Closed,
+                outcomes: VecDeque::new(),
+                opened_at: Instant::now(),
+                probing: false,
+            }),
+        }
+    }
+    pub(super) fn state(&self) -> CircuitState {
+        let mut inner = self.inner.lock().unwrap();
+        self.cool(&mut inner);
+        inner.state
+    }
+    /// 冷却结束后从 `Open` 进入 `HalfOpen`
+    fn cool(&self, inner: &mut Inner) {
+        if inner.state == CircuitState::Open && inner.opened_at.elapsed() >= self.config.cool_down {
+            inner.state = CircuitState::HalfOpen;
+            inner.probing = false;
+        }
+    }
+    /// 是否放行这次调用
+    fn acquire(&self) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        self.cool(&mut inner);
+        match inner.state {
+            CircuitState::Closed => true,
+            CircuitState::Open => false,
+            CircuitState::HalfOpen => !std::mem::replace(&mut inner.probing, true),
+        }
+    }
+    fn record(&self, failed: bool) {
+        let mut inner = self.inner.lock().unwrap();
+        match inner.state {
+            CircuitState::HalfOpen if failed => Self::open(&mut inner),
+            CircuitState::HalfOpen => {
+                inner.state = CircuitState::Closed;
+                inner.outcomes.clear();
+            }
+            CircuitState::Closed => {
+                inner.outcomes.push_back(failed);
+                if inner.outcomes.len() > self.config.window {
+                    inner.outcomes.pop_front();
+                }
+                let failures = inner.outcomes.iter().filter(|&&failed| failed).count();
+                let calls = inner.outcomes.len();
+                if calls >= self.config.min_calls
+                    && failures as f64 >= self.config.failure_rate * calls as f64
+                {
+                    Self::open(&mut inner);
+                }
+            }
+            CircuitState::Open => {}
+        }
+    }
+    /// 没有调用 handler 的尝试（提取失败）不改变状态，只归还试探的名额
+    fn release(&self) {
+        self.inner.lock().unwrap().probing = false;
+    }
+    fn open(inner: &mut Inner) {
+        inner.state = CircuitState::Open;
+        inner.opened_at = Instant::now();
+        inner.outcomes.clear();
+        inner.probing = false;
+    }
+}
+
+/// 调用被放弃（如超时）时按失败记录，避免试探调用一直占着 `HalfOpen`
+struct Guard<'a>(Option<&'a Circuit>);
+impl Guard<'_> {
+    fn finish(mut self, result: &Result<Response, DispatchError>) {
+        if let Some(circuit) = self.0.take() {
+            match result {
+                Err(DispatchError::Extract(_)) => circuit.release(),
+                Err(err) if err.is_rejected() => circuit.release(),
+                result => circuit.record(result.is_err()),
+            }
+        }
+    }
+}
+impl Drop for Guard<'_> {
+    fn drop(&mut self) {
+        if let Some(circuit) = self.0.take() {
+            circuit.record(true);
+        }
+    }
+}
+
+/// 按 `Circuit` 的状态决定是否调用内部的 `Service`
+pub(super) struct CircuitService {
+    inner: Box<dyn Service>,
+    circuit: Arc<Circuit>,
+}
+impl CircuitService {
+    pub(super) fn new(inner: Box<dyn Service>, circuit: Arc<Circuit>) -> Self {
+        Self { inner, circuit }
+    }
+}
+impl Service for CircuitService {
+    fn handle_request<'a>(&'a self, req: Request, trace: &'a Trace) -> ServiceFuture<'a> {
+        Box::pin(async move {
+            if !self.circuit.acquire() {
+                return Err(trace.rejected(DispatchError::CircuitOpen));
+            }
+            let guard = Guard(Some(&self.circuit));
+            let result = self.inner.handle_request(req, trace).await;
+            guard.finish(&result);
+            result
+        })
+    }
+}
@@ -0,0 +1,18 @@
+use super::Extensions;
+
+/// 注册在 `App` 或 scope 上的共享数据，分发时随请求一起交给提取器
+///
+/// 提取器通过 [`Request::config`](super::Request::config) 读取其中的配置，
+/// 例如 `JsonConfig`、`ListConfig`，没有注册时使用各自的默认值
+#[derive(Debug, Clone, Default)]
+pub struct AppContext {
+    configs: Extensions,
+}
+impl AppContext {
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.configs.get()
+    }
+    pub(super) fn insert<T: Send + Sync + 'static>(&mut self, config: T) {
+        self.configs.insert(config);
+    }
+}
@@ -0,0 +1,382 @@
+use std::{fmt, sync::Arc, time::Duration};
+
+use hmac_sha256::HMAC;
+
+use super::{BoxError, ExtractError, FromRequest, Request, Responder, Response};
+
+/// `SameSite` 属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    Strict,
+    Lax,
+    None,
+}
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Strict => "Strict",
+            Self::Lax => "Lax",
+            Self::None => "None",
+        })
+    }
+}
+
+/// 可见的 ASCII 字符，排除 `except` 中的字符
+fn visible_except(s: &str, except: &str) -> bool {
+    s.chars()
+        .all(|c| ('!'..='~').contains(&c) && !except.contains(c))
+}
+
+/// 名字、值或属性不合法，见 `Cookie::try_new`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CookieError {
+    Name(String),
+    Value(String),
+    Attribute { name: &'static str, value: String },
+}
+impl fmt::Display for CookieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Name(name) => write!(f, "invalid cookie name: {:?}", name),
+            Self::Value(value) => write!(f, "invalid cookie value: {:?}", value),
+            Self::Attribute { name, value } => write!(f, "invalid cookie {}: {:?}", name, value),
+        }
+    }
+}
+impl std::error::Error for CookieError {}
+
+/// RFC 6265 的 token，不能为空
+fn check_name(name: String) -> Result<String, CookieError> {
+    if !name.is_empty() && visible_except(&name, "()<>@,;:\\\"/[]?={}") {
+        Ok(name)
+    } else {
+        Err(CookieError::Name(name))
+    }
+}
+/// RFC 6265 的 cookie-octet
+fn check_value(value: String) -> Result<String, CookieError> {
+    if visible_except(&value, "\",;\\") {
+        Ok(value)
+    } else {
+        Err(CookieError::Value(value))
+    }
+}
+/// `Path` 和 `Domain` 的值，不能带入其它属性
+fn check_attribute(name: &'static str, value: String) -> Result<String, CookieError> {
+    if visible_except(&value, ",;") {
+        Ok(value)
+    } else {
+        Err(CookieError::Attribute { name, value })
+    }
+}
+
+/// 一个响应 cookie，`Display` 输出 `Set-Cookie` 的值
+///
+/// 名字、值和 `Path`、`Domain` 不能包含控制字符、空白、`;` 和 `,`，以免被注入其它属性。
+/// 来自客户端的数据应使用 `try_new`、`try_with_path` 和 `try_with_domain`，
+/// 其余方法遇到不合法的输入会 panic
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    name: String,
+    value: String,
+    path: Option<String>,
+    domain: Option<String>,
+    max_age: Option<Duration>,
+    http_only: bool,
+    secure: bool,
+    same_site: Option<SameSite>,
+}
+impl Cookie {
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self::try_new(name, value).unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn try_new(name: impl Into<String>, value: impl Into<String>) -> Result<Self, CookieError> {
+        Ok(Self {
+            name: check_name(name.into())?,
+            value: check_value(value.into())?,
+            path: None,
+            domain: None,
+            max_age: None,
+            http_only: false,
+            secure: false,
+            same_site: None,
+        })
+    }
+    pub fn with_path(self, path: impl Into<String>) -> Self {
+        self.try_with_path(path).unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn try_with_path(mut self, path: impl Into<String>) -> Result<Self, CookieError> {
+        self.path = Some(check_attribute("path", path.into())?);
+        Ok(self)
+    }
+    pub fn with_domain(self, domain: impl Into<String>) -> Self {
+        self.try_with_domain(domain)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+    pub fn try_with_domain(mut self, domain: impl Into<String>) -> Result<Self, CookieError> {
+        self.domain = Some(check_attribute("domain", domain.into())?);
+        Ok(self)
+    }
+    /// 只精确到秒
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+    pub fn with_http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+    pub fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+    pub fn max_age(&self) -> Option<Duration> {
+        self.max_age
+    }
+}
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(path) = &self.path {
+            write!(f, "; Path={}", path)?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={}", domain)?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={}", max_age.as_secs())?;
+        }
+        if self.http_only {
+            f.write_str("; HttpOnly")?;
+        }
+        // 浏览器要求 `SameSite=None` 必须同时带上 `Secure`
+        if self.secure || self.same_site == Some(SameSite::None) {
+            f.write_str("; Secure")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={}", same_site)?;
+        }
+        Ok(())
+    }
+}
+
+/// 签名 cookie 使用的 HMAC-SHA256 密钥，通过 `App::extractor_config` 设置
+#[derive(Clone)]
+pub struct CookieKey(Arc<[u8]>);
+impl CookieKey {
+    pub fn new(secret: impl AsRef<[u8]>) -> Self {
+        Self(secret.as_ref().into())
+    }
+    /// 签名同时覆盖名字和值，防止把一个 cookie 的值挪到另一个名下
+    fn mac(&self, name: &str, value: &str) -> [u8; 32] {
+        let mut hmac = HMAC::new(&self.0);
+        hmac.update(name);
+        hmac.update("=");
+        hmac.update(value);
+        hmac.finalize()
+    }
+    fn sign(&self, name: &str, value: &str) -> String {
+        let hex: String = self
+            .mac(name, value)
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        format!("{}.{}", value, hex)
+    }
+    fn verify<'a>(&self, name: &str, signed: &'a str) -> Option<&'a str> {
+        let (value, hex) = signed.rsplit_once('.')?;
+        if hex.len() != 64 {
+            return None;
+        }
+        let mut mac = [0u8; 32];
+        for (i, byte) in mac.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(hex.get(i * 2..i * 2 + 2)?, 16).ok()?;
+        }
+        let mut hmac = HMAC::new(&self.0);
+        hmac.update(name);
+        hmac.update("=");
+        hmac.update(value);
+        if hmac.finalize_verify(&mac) {
+            Some(value)
+        } else {
+            None
+        }
+    }
+}
+impl fmt::Debug for CookieKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CookieKey(***)")
+    }
+}
+
+/// 请求中的 cookie，以及 handler 对它们的修改
+///
+/// 作为 `(Cookies, T)` 返回时，修改过的 cookie 会写入 `Set-Cookie`
+#[derive(Debug, Clone, Default)]
+pub struct Cookies {
+    original: Vec<(String, String)>,
+    delta: Vec<Cookie>,
+    key: Option<CookieKey>,
+}
+impl Cookies {
+    /// 名为 `name` 的 cookie 的值，包括本次修改过的
+    pub fn get(&self, name: &str) -> Option<&str> {
+        match self.delta.iter().find(|cookie| cookie.name == name) {
+            Some(cookie) if cookie.max_age == Some(Duration::from_secs(0)) => None,
+            Some(cookie) => Some(&cookie.value),
+            None => self
+                .original
+                .iter()
+                .find(|(k, _)| k == name)
+                .map(|(_, v)| v.as_str()),
+        }
+    }
+    /// 添加或修改 cookie，同名的修改只保留最后一次
+    pub fn add(&mut self, cookie: Cookie) {
+        self.delta.retain(|c| c.name != cookie.name);
+        self.delta.push(cookie);
+    }
+    /// 让浏览器删除 cookie，`path` 和 `domain` 需要与设置时一致
+    pub fn remove(&mut self, cookie: Cookie) {
+        self.add(Cookie {
+            value: String::new(),
+            max_age: Some(Duration::from_secs(0)),
+            ..cookie
+        });
+    }
+    /// 校验签名并返回原始的值，签名不对或没有设置密钥时返回 `None`
+    pub fn get_signed(&self, name: &str) -> Option<&str> {
+        self.key.as_ref()?.verify(name, self.get(name)?)
+    }
+    /// 对值签名后添加，没有设置 `CookieKey` 时失败
+    pub fn add_signed(&mut self, mut cookie: Cookie) -> Result<(), BoxError> {
+        let key = self.key.as_ref().ok_or("no `CookieKey` is configured")?;
+        cookie.value = key.sign(&cookie.name, &cookie.value);
+        self.add(cookie);
+        Ok(())
+    }
+    /// 请求中带来的全部 cookie
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.original.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+    /// 需要写入 `Set-Cookie` 的修改
+    pub fn delta(&self) -> impl Iterator<Item = &Cookie> {
+        self.delta.iter()
+    }
+}
+impl FromRequest for Cookies {
+    type Error = ExtractError;
+    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+        let original = req
+            .headers()
+            .filter(|(k, _)| k.eq_ignore_ascii_case("Cookie"))
+            .flat_map(|(_, v)| v.split(';'))
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(k, v)| (k.trim().to_string(), v.trim().trim_matches('"').to_string()))
+            .collect();
+        Ok(Self {
+            original,
+            delta: vec![],
+            key: req.config::<CookieKey>().cloned(),
+        })
+    }
+}
+
+impl Response {
+    pub fn with_cookie(self, cookie: &Cookie) -> Self {
+        self.with_header("Set-Cookie", cookie.to_string())
+    }
+}
+// 把 handler 对 cookie 的修改写入响应
+impl<T> Responder for (Cookies, T)
+where
+    T: Responder,
+{
+    fn respond_to(self) -> Result<Response, BoxError> {
+        let response = self.1.respond_to()?;
+        Ok(self.0.delta().fold(response, Response::with_cookie))
+    }
+}
+
+#[test]
+fn test_cookies() {
+    let req = Request::new("")
+        .with_header("Cookie", "theme=dark; session=\"abc\"")
+        .with_extension(CookieKey::new("secret"));
+    let mut cookies = Cookies::from_request(&req).unwrap();
+    assert_eq!(cookies.get("theme"), Some("dark"));
+    assert_eq!(cookies.get("session"), Some("abc"));
+
+    cookies.add(Cookie::new("theme", "light").with_path("/"));
+    cookies.remove(Cookie::new("session", "").with_path("/"));
+    cookies
+        .add_signed(
+            Cookie::new("user", "42")
+                .with_max_age(Duration::from_secs(3600))
+                .with_http_only(true)
+                .with_same_site(SameSite::Lax),
+        )
+        .unwrap();
+    assert_eq!(cookies.get("theme"), Some("light"));
+    assert_eq!(cookies.get("session"), None);
+    assert_eq!(cookies.get_signed("user"), Some("42"));
+
+    let response = (cookies, "ok").respond_to().unwrap();
+    let set_cookie: Vec<_> = response
+        .headers()
+        .filter(|(k, _)| *k == "Set-Cookie")
+        .map(|(_, v)| v)
+        .collect();
+    assert_eq!(set_cookie[0], "theme=light; Path=/");
+    assert_eq!(set_cookie[1], "session=; Path=/; Max-Age=0");
+    assert!(set_cookie[2].starts_with("user=42."));
+    assert!(set_cookie[2].ends_with("; Max-Age=3600; HttpOnly; SameSite=Lax"));
+
+    // 篡改值或换一个名字都会导致签名校验失败
+    let signed = set_cookie[2].split(';').next().unwrap();
+    let cookie = |value: &str| {
+        let req = Request::new("")
+            .with_header("Cookie", value)
+            .with_extension(CookieKey::new("secret"));
+        Cookies::from_request(&req).unwrap()
+    };
+    assert_eq!(cookie(signed).get_signed("user"), Some("42"));
+    assert_eq!(cookie(&signed.replace("42", "43")).get_signed("user"), None);
+    assert_eq!(
+        cookie(&signed.replace("user", "admin")).get_signed("admin"),
+        None
+    );
+    assert!(Cookies::default()
+        .add_signed(Cookie::new("a", "b"))
+        .is_err());
+
+    // 不能通过值或属性注入其它属性
+    assert_eq!(
+        Cookie::try_new("a", "x; Domain=evil"),
+        Err(CookieError::Value("x; Domain=evil".to_string()))
+    );
+    assert!(Cookie::try_new("a", "x\r\nSet-Cookie: b=1").is_err());
+    assert!(matches!(
+        Cookie::try_new("a b", "x"),
+        Err(CookieError::Name(_))
+    ));
+    let cookie = Cookie::new("a", "x");
+    assert!(cookie.clone().try_with_path("/; Secure").is_err());
+    let err = cookie.try_with_domain("example.com,evil").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "invalid cookie domain: \"example.com,evil\""
+    );
+    assert!(std::panic::catch_unwind(|| Cookie::new("a", "x;")).is_err());
+}
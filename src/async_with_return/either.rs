@@ -0,0 +1,199 @@
+use std::{future::Future, pin::Pin};
+
+use super::{
+    BoxError, ExtractError, ExtractFuture, FromRequest, FromRequestBody, Request, Responder,
+    Response, ViaBody,
+};
+
+/// 先尝试提取 `A`，失败时再尝试 `B`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Either<A, B> {
+    Left(A),
+    Right(B),
+}
+
+/// 收集每个备选的错误，嵌套的备选展开为同一层
+fn push_error(errors: &mut Vec<ExtractError>, error: ExtractError) {
+    match error {
+        ExtractError::Alternatives(nested) => errors.extend(nested),
+        error => errors.push(error),
+    }
+}
+
+impl<A, B> FromRequest for Either<A, B>
+where
+    A: FromRequest,
+    B: FromRequest,
+{
+    type Error = ExtractError;
+    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+        let mut errors = vec![];
+        match A::from_request(req) {
+            Ok(a) => return Ok(Either::Left(a)),
+            Err(e) => push_error(&mut errors, e.into()),
+        }
+        match B::from_request(req) {
+            Ok(b) => return Ok(Either::Right(b)),
+            Err(e) => push_error(&mut errors, e.into()),
+        }
+        Err(ExtractError::Alternatives(errors))
+    }
+}
+impl<A, B> Responder for Either<A, B>
+where
+    A: Responder,
+    B: Responder,
+{
+    fn respond_to(self) -> Result<Response, BoxError> {
+        match self {
+            Either::Left(a) => a.respond_to(),
+            Either::Right(b) => b.respond_to(),
+        }
+    }
+}
+
+/// 由元组 `(A, B, ..)` 实现，依次尝试每个备选
+pub trait Alternatives {
+    /// 只有成功的那个备选为 `Some`
+    type Options;
+}
+
+/// 按 `M` 中每个备选的 `FromRequestBody` 标记提取 `Alternatives`，供 `OneOf` 使用
+pub trait ExtractFirst<M>: Alternatives {
+    /// 返回第一个成功的备选的下标，全部失败时返回每个备选的错误
+    ///
+    /// 每个备选都拿到完整的请求体，成功的备选取走它
+    fn extract_first(req: &mut Request) -> AlternativesFuture<'_, Self::Options>;
+}
+
+type AlternativesFuture<'r, T> =
+    Pin<Box<dyn Future<Output = Result<(usize, T), Vec<ExtractError>>> + 'r>>;
+
+/// 依次尝试 `T` 中的每个类型，取第一个提取成功的
+///
+/// 备选可以取走请求体，所以 `OneOf` 只能作为 handler 的最后一个参数
+///
+/// ```ignore
+/// async fn create(user: OneOf<(Json<User>, Form<User>)>) {
+///     match user.into_inner() {
+///         (Some(Json(user)), _) | (_, Some(Form(user))) => {}
+///         _ => unreachable!(),
+///     }
+/// }
+/// ```
+pub struct OneOf<T: Alternatives> {
+    index: usize,
+    options: T::Options,
+}
+impl<T: Alternatives> OneOf<T> {
+    /// 成功的备选在 `T` 中的下标
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    pub fn into_inner(self) -> T::Options {
+        self.options
+    }
+}
+impl<T, M> FromRequestBody<ViaBody<M>> for OneOf<T>
+where
+    T: ExtractFirst<M> + 'static,
+{
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        Box::pin(async move {
+            T::extract_first(req)
+                .await
+                .map(|(index, options)| OneOf { index, options })
+                .map_err(ExtractError::Alternatives)
+        })
+    }
+}
+
+#[rustfmt::skip]
+mod _impl_alternatives {
+    use super::*;
+
+    fn none<T>() -> Option<T> {
+        None
+    }
+    macro_rules! f {
+        (($($Ts:ident),*), ($($Ms:ident),*), ($($Ns:tt),*)) => {
+            impl< $($Ts,)* > Alternatives for ( $($Ts,)* ) {
+                type Options = ( $(Option<$Ts>,)* );
+            }
+            impl< $($Ts,)* $($Ms,)* > ExtractFirst<( $($Ms,)* )> for ( $($Ts,)* )
+            where
+                $(
+                    $Ts: FromRequestBody<$Ms> + 'static,
+                )*
+            {
+                fn extract_first(req: &mut Request) -> AlternativesFuture<'_, Self::Options> {
+                    Box::pin(async move {
+                        let empty = || ( $(none::<$Ts>(),)* );
+                        let body = req.take_body();
+                        let mut errors = vec![];
+                        $(
+                            req.put_body(&body);
+                            match $Ts::from_request_body(req).await {
+                                Ok(value) => {
+                                    let mut options = empty();
+                                    options.$Ns = Some(value);
+                                    return Ok(($Ns, options));
+                                }
+                                Err(e) => push_error(&mut errors, e),
+                            }
+                        )*
+                        Err(errors)
+                    })
+                }
+            }
+        };
+    }
+    all_tuples_with_markers!(f);
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_either() {
+    use super::{Form, Json, Parsed, Query};
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct User {
+        name: String,
+    }
+
+    let by_id = Request::new("42");
+    let by_name = Request::new("alice");
+    assert_eq!(
+        Either::<u32, Parsed<String>>::from_request(&by_id).unwrap(),
+        Either::Left(42)
+    );
+    assert_eq!(
+        Either::<u32, Parsed<String>>::from_request(&by_name).unwrap(),
+        Either::Right(Parsed("alice".to_string()))
+    );
+    // 每个备选的错误都会保留，嵌套的 Either 也会展开
+    let err = Either::<u32, Either<bool, char>>::from_request(&by_name).unwrap_err();
+    match &err {
+        ExtractError::Alternatives(errors) => assert_eq!(errors.len(), 3),
+        err => panic!("unexpected error: {}", err),
+    }
+    assert!(err.to_string().contains("`bool`"));
+
+    // 失败的备选取走的请求体会放回，下一个备选仍然能读到
+    type Body = (Json<User>, Form<User>, Query<User>);
+    let mut form =
+        Request::new("name=bob").with_header("Content-Type", "application/x-www-form-urlencoded");
+    let one_of = OneOf::<Body>::from_request_body(&mut form).await.unwrap();
+    assert_eq!(one_of.index(), 1);
+    assert!(form.body().is_empty());
+    match one_of.into_inner() {
+        (None, Some(Form(user)), None) => assert_eq!(user.name, "bob"),
+        _ => panic!("expected the form alternative"),
+    }
+    let err = OneOf::<Body>::from_request_body(&mut Request::new(""))
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, ExtractError::Alternatives(errors) if errors.len() == 3));
+}
@@ -0,0 +1,67 @@
+use std::{error::Error, fmt, time::Duration};
+
+use super::{BoxError, ExtractError};
+
+/// handler 没有产生响应的原因
+#[derive(Debug)]
+pub enum DispatchError {
+    /// 参数提取失败，handler 被跳过
+    Extract(ExtractError),
+    /// handler 返回了错误，或者返回值无法转换为响应
+    Handler(BoxError),
+    /// 提取或 handler panic 了，保存 panic 的消息
+    Panic(String),
+    /// handler 在设置的时间内没有完成，已被放弃
+    Timeout(Duration),
+    /// handler 的熔断器处于打开状态，没有调用 handler
+    CircuitOpen,
+    /// 超出 `RateLimit`
+    RateLimited,
+    /// 超出 `ConcurrencyLimit`
+    ConcurrencyLimited,
+}
+impl fmt::Display for DispatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Extract(e) => write!(f, "extraction failed: {}", e),
+            Self::Handler(e) => write!(f, "handler failed: {}", e),
+            Self::Panic(message) => write!(f, "handler panicked: {}", message),
+            Self::Timeout(duration) => write!(f, "handler timed out after {:?}", duration),
+            Self::CircuitOpen => write!(f, "circuit open, handler skipped"),
+            Self::RateLimited => write!(f, "rate limit exceeded"),
+            Self::ConcurrencyLimited => write!(f, "too many concurrent calls"),
+        }
+    }
+}
+impl DispatchError {
+    /// 沿着 `source` 链查找第一个类型为 `E` 的错误，包括自身
+    pub fn downcast_ref<E: Error + 'static>(&self) -> Option<&E> {
+        let mut current: Option<&(dyn Error + 'static)> = Some(self);
+        while let Some(err) = current {
+            if let Some(err) = err.downcast_ref() {
+                return Some(err);
+            }
+            current = err.source();
+        }
+        None
+    }
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, Self::Timeout(_))
+    }
+    /// 被限流或熔断拒绝，handler 没有执行
+    pub fn is_rejected(&self) -> bool {
+        matches!(
+            self,
+            Self::CircuitOpen | Self::RateLimited | Self::ConcurrencyLimited
+        )
+    }
+}
+impl Error for DispatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Extract(e) => Some(e),
+            Self::Handler(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
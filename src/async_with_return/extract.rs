@@ -0,0 +1,519 @@
+use std::{
+    any::type_name,
+    borrow::Cow,
+    convert::Infallible,
+    error::Error,
+    fmt,
+    future::{self, Future},
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    pin::Pin,
+    str::FromStr,
+};
+
+use super::{Request, ValidationErrors};
+
+pub type BoxError = Box<dyn Error + Send + Sync>;
+
+/// 从请求中提取参数失败
+#[derive(Debug)]
+pub enum ExtractError {
+    /// 请求无法解析为 `ty`，`source` 为 `FromStr::Err`
+    Parse { ty: &'static str, source: BoxError },
+    /// 请求中没有名为 `name` 的参数
+    Missing { source: Source, name: String },
+    /// 请求体超过了配置的大小限制
+    PayloadTooLarge { size: usize, limit: usize },
+    /// `Content-Type` 不符合要求
+    ContentType {
+        expected: &'static str,
+        found: Option<String>,
+    },
+    /// 请求体不是合法的 JSON，`offset` 为出错位置的字节偏移
+    Json {
+        ty: &'static str,
+        offset: usize,
+        source: serde_json::Error,
+    },
+    /// 请求头 `name` 的值格式错误
+    Header {
+        name: &'static str,
+        source: BoxError,
+    },
+    /// 提取成功但没有通过 `Validate` 校验
+    Invalid(ValidationErrors),
+    /// 列表中下标为 `index` 的元素提取失败
+    Element {
+        index: usize,
+        source: Box<ExtractError>,
+    },
+    /// 所有备选都提取失败，按顺序保存每个备选的错误
+    Alternatives(Vec<ExtractError>),
+    /// `multipart/form-data` 格式错误
+    Multipart { reason: &'static str },
+    /// `multipart/form-data` 中名为 `name` 的部分超过了大小限制
+    PartTooLarge {
+        name: Option<String>,
+        size: usize,
+        limit: usize,
+    },
+}
+impl ExtractError {
+    pub fn parse<T>(source: impl Into<BoxError>) -> Self {
+        Self::Parse {
+            ty: type_name::<T>(),
+            source: source.into(),
+        }
+    }
+}
+impl fmt::Display for ExtractError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Parse { ty, source } => write!(f, "cannot parse request as `{}`: {}", ty, source),
+            Self::Missing { source, name } => write!(f, "missing {} `{}`", source, name),
+            Self::PayloadTooLarge { size, limit } => {
+                write!(
+                    f,
+                    "payload of {} bytes exceeds the limit of {} bytes",
+                    size, limit
+                )
+            }
+            Self::ContentType { expected, found } => match found {
+                Some(found) => write!(f, "expected content type `{}`, found `{}`", expected, found),
+                None => write!(f, "expected content type `{}`, found none", expected),
+            },
+            Self::Json { ty, offset, source } => write!(
+                f,
+                "cannot parse JSON body as `{}` at byte {}: {}",
+                ty, offset, source
+            ),
+            Self::Header { name, source } => write!(f, "invalid header `{}`: {}", name, source),
+            Self::Invalid(errors) => write!(f, "validation failed: {}", errors),
+            Self::Element { index, source } => write!(f, "element #{}: {}", index, source),
+            Self::Alternatives(errors) => {
+                write!(f, "all {} alternatives failed", errors.len())?;
+                for (i, error) in errors.iter().enumerate() {
+                    write!(f, "; #{}: {}", i, error)?;
+                }
+                Ok(())
+            }
+            Self::Multipart { reason } => write!(f, "malformed multipart body: {}", reason),
+            Self::PartTooLarge { name, size, limit } => write!(
+                f,
+                "multipart part `{}` of {} bytes exceeds the limit of {} bytes",
+                name.as_deref().unwrap_or(""),
+                size,
+                limit
+            ),
+        }
+    }
+}
+impl From<Infallible> for ExtractError {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+impl Error for ExtractError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Parse { source, .. } | Self::Header { source, .. } => Some(source.as_ref()),
+            Self::Json { source, .. } => Some(source),
+            Self::Invalid(errors) => Some(errors),
+            Self::Element { source, .. } => Some(source.as_ref()),
+            Self::Missing { .. }
+            | Self::PayloadTooLarge { .. }
+            | Self::ContentType { .. }
+            | Self::Alternatives(_)
+            | Self::Multipart { .. }
+            | Self::PartTooLarge { .. } => None,
+        }
+    }
+}
+
+/// 要求 T 可解析
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be extracted from a request",
+    note = "implement `FromRequest` for it, or `FromRequestParts` if extraction needs to await",
+    note = "`FromRequestBody` extractors such as `Body` or `Json<T>` are only accepted as the last handler parameter"
+)]
+pub trait FromRequest: Sized {
+    /// 提取失败时的错误，组合提取时统一转换为 `ExtractError`
+    type Error: Into<ExtractError>;
+    fn from_request(req: &Request) -> Result<Self, Self::Error>;
+}
+
+pub type ExtractFuture<'r, T> = Pin<Box<dyn Future<Output = Result<T, ExtractError>> + 'r>>;
+
+/// 只读取请求、可以出现在 handler 任意位置的提取，可以等待，例如从缓存中加载用户
+///
+/// 所有 `FromRequest` 都自动实现了这个 trait，包括同步提取器组成的元组，
+/// 所以 `Option<(A, B)>` 这样的同步组合不受影响；需要等待的提取器直接作为 handler 的参数，
+/// 由 `HandlerParams` 按顺序等待
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be extracted from a shared request",
+    note = "extractors that consume the body, like `Body` or `Json<T>`, must be the last handler parameter"
+)]
+pub trait FromRequestParts: Sized {
+    fn from_request_parts(req: &Request) -> ExtractFuture<'_, Self>;
+}
+impl<T> FromRequestParts for T
+where
+    T: FromRequest + 'static,
+{
+    fn from_request_parts(req: &Request) -> ExtractFuture<'_, Self> {
+        Box::pin(future::ready(T::from_request(req).map_err(Into::into)))
+    }
+}
+
+/// 取走请求体的提取，只能作为 handler 的最后一个参数
+///
+/// `Body`、`String`、`Vec<u8>`、`Json<T>`、`Form<T>`、`Multipart` 等读取请求体的提取器只实现这个 trait，
+/// 通过 `Request::take_body` 拿走请求体，所以每次调用只有一个提取器能读到它。
+/// `M` 区分实现的来源：所有 `FromRequestParts` 都以 `ViaParts` 自动实现，
+/// 取走请求体的实现使用 `ViaBody`，两者不会重叠
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be extracted as the last handler parameter",
+    note = "implement `FromRequest` or `FromRequestParts` for it, or `FromRequestBody` if it consumes the body"
+)]
+pub trait FromRequestBody<M = ViaBody>: Sized {
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self>;
+}
+impl<T> FromRequestBody<ViaParts> for T
+where
+    T: FromRequestParts,
+{
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        T::from_request_parts(req)
+    }
+}
+
+/// `FromRequestBody` 的标记，表示通过 `FromRequestParts` 实现，不取走请求体
+pub enum ViaParts {}
+
+/// `FromRequestBody` 的标记，表示取走请求体；`M` 为组合子内部提取器的标记
+pub struct ViaBody<M = ()>(Infallible, PhantomData<M>);
+
+/// handler 的参数列表，前面的参数共享请求，最后一个参数可以取走请求体
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` cannot be used as handler parameters",
+    note = "extractors that consume the body, like `Body` or `Json<T>`, must be the last handler parameter"
+)]
+pub trait HandlerParams<M>: Sized {
+    fn extract_params(req: &mut Request) -> ExtractFuture<'_, Self>;
+}
+
+/// 提取 `String` 时的配置，通过 `App::extractor_config` 设置
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StringConfig {
+    /// 去掉请求体两侧的空白，默认不去掉
+    pub trim: bool,
+}
+
+/// 直接提取整数参数时使用的进制，通过 `App::extractor_config` 设置
+///
+/// 只影响 `u32` 等整数本身，`Parsed<T>` 和字段属性 `#[from_request(query)]` 仍按十进制解析
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntegerConfig {
+    /// 2 到 36，默认为 10
+    pub radix: u32,
+}
+impl Default for IntegerConfig {
+    fn default() -> Self {
+        Self { radix: 10 }
+    }
+}
+
+/// 任意实现了 `FromStr` 的类型都可以通过 `Parsed<T>` 提取
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Parsed<T>(pub T);
+impl<T> Parsed<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<T> Deref for Parsed<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for Parsed<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+impl<T> FromRequest for Parsed<T>
+where
+    T: FromStr,
+    T::Err: Into<BoxError>,
+{
+    type Error = ExtractError;
+    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+        Source::Body.extract(req, "").map(Parsed)
+    }
+}
+
+/// 参数在请求中的来源，供 `#[derive(FromRequest)]` 的字段属性使用
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Path,
+    Query,
+    Header,
+    Body,
+}
+impl Source {
+    fn lookup<'r>(self, req: &'r Request, name: &str) -> Option<Cow<'r, str>> {
+        match self {
+            Self::Path => req.param(name).map(Cow::Borrowed),
+            Self::Query => req.query_param(name),
+            Self::Header => req.header(name).map(Cow::Borrowed),
+            Self::Body => req.body_str().ok().map(Cow::Borrowed),
+        }
+    }
+    /// 从请求中取出名为 `name` 的值并解析为 `T`，`Body` 会忽略 `name`
+    ///
+    /// `Body` 只把请求体当作文本解析而不取走它，所以整数、`Parsed<T>` 等标量可以出现在任意位置
+    pub fn extract<T>(self, req: &Request, name: &str) -> Result<T, ExtractError>
+    where
+        T: FromStr,
+        T::Err: Into<BoxError>,
+    {
+        if self == Self::Body {
+            // 区分请求体不是 UTF-8 的情况
+            req.body_str().map_err(ExtractError::parse::<T>)?;
+        }
+        let s = self.lookup(req, name).ok_or_else(|| ExtractError::Missing {
+            source: self,
+            name: name.to_string(),
+        })?;
+        s.parse().map_err(ExtractError::parse::<T>)
+    }
+}
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Path => "path parameter",
+            Self::Query => "query parameter",
+            Self::Header => "header",
+            Self::Body => "body",
+        })
+    }
+}
+
+#[rustfmt::skip]
+mod _impl_from_request {
+    use super::*;
+    use std::net::*;
+    use std::num::*;
+
+    impl FromRequest for () {
+        type Error = Infallible;
+        fn from_request(req: &Request) -> Result<Self, Self::Error> {
+            Ok(())
+        }
+    }
+    // 提取失败时得到 None，而不是跳过整个 handler
+    impl<T> FromRequest for Option<T>
+    where
+        T: FromRequest,
+    {
+        type Error = Infallible;
+        fn from_request(req: &Request) -> Result<Self, Self::Error> {
+            Ok(T::from_request(req).ok())
+        }
+    }
+    // 将提取错误交给 handler 自行处理
+    impl<T> FromRequest for Result<T, T::Error>
+    where
+        T: FromRequest,
+    {
+        type Error = Infallible;
+        fn from_request(req: &Request) -> Result<Self, Self::Error> {
+            Ok(T::from_request(req))
+        }
+    }
+    // 标准库中的 FromStr 类型都委托给 Parsed
+    macro_rules! parsed {
+        ($($T:ty),*) => {
+            $(
+                impl FromRequest for $T {
+                    type Error = ExtractError;
+                    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+                        Parsed::<$T>::from_request(req).map(Parsed::into_inner)
+                    }
+                }
+            )*
+        };
+    }
+    // 整数按 IntegerConfig 的进制解析
+    macro_rules! integer {
+        ($($T:ty),*) => {
+            $(
+                impl FromRequest for $T {
+                    type Error = ExtractError;
+                    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+                        let radix = match req.config::<IntegerConfig>() {
+                            Some(config) if config.radix != 10 => config.radix,
+                            _ => return Parsed::<$T>::from_request(req).map(Parsed::into_inner),
+                        };
+                        if !(2..=36).contains(&radix) {
+                            return Err(ExtractError::parse::<$T>(format!("invalid radix {}", radix)));
+                        }
+                        let s = req.body_str().map_err(ExtractError::parse::<$T>)?;
+                        <$T>::from_str_radix(s, radix).map_err(ExtractError::parse::<$T>)
+                    }
+                }
+            )*
+        };
+    }
+    integer!(u8, u16, u32, u64, u128, usize);
+    integer!(i8, i16, i32, i64, i128, isize);
+    parsed!(f32, f64, bool, char);
+    parsed!(NonZeroU8, NonZeroU16, NonZeroU32, NonZeroU64, NonZeroU128, NonZeroUsize);
+    parsed!(NonZeroI8, NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI128, NonZeroIsize);
+    parsed!(IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV4, SocketAddrV6);
+    // propagate
+    macro_rules! f {
+        (($($Ts:ident),*), ($($Ns:tt),*)) => {
+            impl< $($Ts,)* > FromRequest for ( $($Ts,)* )
+            where
+                $(
+                    $Ts: FromRequest,
+                )*
+            {
+                type Error = ExtractError;
+                fn from_request(req: &Request) -> Result<Self, Self::Error> {
+                    Ok((
+                        $(
+                            $Ts::from_request(req).map_err(Into::into)?,
+                        )*
+                    ))
+                }
+            }
+        };
+    }
+    all_tuples!(f);
+}
+
+#[rustfmt::skip]
+mod _impl_from_request_body {
+    use super::*;
+
+    impl FromRequestBody for String {
+        fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+            let trim = req.config::<StringConfig>().is_some_and(|config| config.trim);
+            let s = req.take_body().into_string().map_err(ExtractError::parse::<String>);
+            Box::pin(future::ready(s.map(|s| if trim { s.trim().to_string() } else { s })))
+        }
+    }
+    impl FromRequestBody for Vec<u8> {
+        fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+            Box::pin(future::ready(Ok(req.take_body().into_bytes())))
+        }
+    }
+    // 与同步的 Option 和 Result 相同，但内部的提取器取走请求体
+    impl<T, M> FromRequestBody<ViaBody<M>> for Option<T>
+    where
+        T: FromRequestBody<ViaBody<M>> + 'static,
+    {
+        fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+            Box::pin(async move { Ok(T::from_request_body(req).await.ok()) })
+        }
+    }
+    impl<T, M> FromRequestBody<ViaBody<M>> for Result<T, ExtractError>
+    where
+        T: FromRequestBody<ViaBody<M>> + 'static,
+    {
+        fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+            Box::pin(async move { Ok(T::from_request_body(req).await) })
+        }
+    }
+}
+
+#[rustfmt::skip]
+mod _impl_handler_params {
+    use super::*;
+
+    impl HandlerParams<ViaParts> for () {
+        fn extract_params(req: &mut Request) -> ExtractFuture<'_, Self> {
+            Box::pin(future::ready(Ok(())))
+        }
+    }
+    // 前面的参数只能共享请求，只有最后一个参数可以取走请求体
+    macro_rules! f {
+        (($($Ts:ident),*), $Last:ident, ($($Ns:tt),*), $N:tt) => {
+            impl< $($Ts,)* $Last, M > HandlerParams<M> for ( $($Ts,)* $Last, )
+            where
+                $(
+                    $Ts: FromRequestParts + 'static,
+                )*
+                $Last: FromRequestBody<M> + 'static,
+            {
+                fn extract_params(req: &mut Request) -> ExtractFuture<'_, Self> {
+                    Box::pin(async move {
+                        Ok((
+                            $(
+                                $Ts::from_request_parts(req).await?,
+                            )*
+                            $Last::from_request_body(req).await?,
+                        ))
+                    })
+                }
+            }
+        };
+    }
+    all_tuples_with_last!(f);
+}
+
+#[test]
+fn test_parsed() {
+    use std::net::IpAddr;
+    use std::num::{ParseFloatError, ParseIntError};
+
+    let req = Request::new("-42");
+    assert_eq!(i64::from_request(&req).unwrap(), -42);
+    assert_eq!(f32::from_request(&req).unwrap(), -42.0);
+    assert_eq!(Parsed::<String>::from_request(&req).unwrap().0, "-42");
+
+    // 错误中携带 FromStr::Err
+    let err = u32::from_request(&req).unwrap_err();
+    assert!(err.to_string().contains("`u32`"));
+    let source = err.source().unwrap();
+    assert!(source.downcast_ref::<ParseIntError>().is_some());
+
+    let req = Request::new("::1");
+    let Parsed(ip) = Parsed::<IpAddr>::from_request(&req).unwrap();
+    assert!(ip.is_loopback());
+    let err = f64::from_request(&req).unwrap_err();
+    assert!(err.source().unwrap().is::<ParseFloatError>());
+}
+
+#[test]
+fn test_optional() {
+    let req = Request::new("abc");
+    assert_eq!(Option::<u32>::from_request(&req).unwrap(), None);
+    assert_eq!(
+        Option::<Parsed<String>>::from_request(&req).unwrap(),
+        Some(Parsed("abc".to_string()))
+    );
+
+    // 单个参数失败不再导致整个元组失败
+    let (n, Parsed(s)) = <(Option<u32>, Parsed<String>)>::from_request(&req).unwrap();
+    assert_eq!(n, None);
+    assert_eq!(s, "abc");
+
+    // 同步的元组可以继续与其它组合子嵌套
+    let seven = Request::new("7");
+    assert_eq!(
+        Option::<(u32, i64)>::from_request(&seven).unwrap(),
+        Some((7, 7))
+    );
+    assert_eq!(Option::<(u32, bool)>::from_request(&seven).unwrap(), None);
+    let either = super::Either::<(bool, u8), u32>::from_request(&seven).unwrap();
+    assert_eq!(either, super::Either::Right(7));
+
+    let r = Result::<u32, ExtractError>::from_request(&req).unwrap();
+    assert!(matches!(r, Err(ExtractError::Parse { ty: "u32", .. })));
+    let r = Result::<u32, ExtractError>::from_request(&Request::new("7")).unwrap();
+    assert_eq!(r.unwrap(), 7);
+}
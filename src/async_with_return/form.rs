@@ -0,0 +1,96 @@
+use std::{
+    future,
+    ops::{Deref, DerefMut},
+    str,
+};
+
+use serde::de::DeserializeOwned;
+
+use super::{urlencoded, ExtractError, ExtractFuture, FromRequestBody, Request};
+
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// 将 `application/x-www-form-urlencoded` 的请求体反序列化为 `T`，
+/// 与 `Query<T>` 使用同样的解码规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Form<T>(pub T);
+impl<T> Form<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<T> Deref for Form<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for Form<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+impl<T> FromRequestBody for Form<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        Box::pin(future::ready(Self::extract(req)))
+    }
+}
+impl<T> Form<T>
+where
+    T: DeserializeOwned,
+{
+    fn extract(req: &mut Request) -> Result<Self, ExtractError> {
+        let content_type = req.header("Content-Type");
+        let mime = content_type
+            .and_then(|s| s.split(';').next())
+            .map(str::trim);
+        if !mime.is_some_and(|mime| mime.eq_ignore_ascii_case(FORM_CONTENT_TYPE)) {
+            return Err(ExtractError::ContentType {
+                expected: FORM_CONTENT_TYPE,
+                found: content_type.map(str::to_string),
+            });
+        }
+        let body = req.take_body();
+        let body = str::from_utf8(body.bytes()).map_err(ExtractError::parse::<T>)?;
+        urlencoded::from_str(body)
+            .map(Form)
+            .map_err(ExtractError::parse::<T>)
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_form() {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Comment {
+        author: String,
+        text: String,
+        tags: Vec<String>,
+    }
+
+    let req = Request::new("author=alice&text=hello+world%21&tags=a&tags=b").with_header(
+        "Content-Type",
+        "application/x-www-form-urlencoded; charset=utf-8",
+    );
+    let Form(comment) = Form::<Comment>::from_request_body(&mut req.clone())
+        .await
+        .unwrap();
+    assert_eq!(comment.text, "hello world!");
+    assert_eq!(comment.tags, ["a", "b"]);
+
+    let Form(pairs) = Form::<Vec<(String, String)>>::from_request_body(&mut req.clone())
+        .await
+        .unwrap();
+    assert_eq!(pairs.len(), 4);
+
+    let mut req = Request::new("author=alice").with_header("Content-Type", "application/json");
+    let err = Form::<Comment>::from_request_body(&mut req)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ExtractError::ContentType { .. }));
+}
@@ -0,0 +1,266 @@
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use super::{BoxError, ExtractError, FromRequest, Request, Response, Source};
+
+/// 强类型的请求头，负责与头的字符串值互相转换
+pub trait TypedHeader: Sized {
+    /// 头的名字，比较时忽略大小写
+    const NAME: &'static str;
+    fn decode(value: &str) -> Result<Self, BoxError>;
+    fn encode(&self) -> String;
+}
+
+/// 提取并解析名为 `T::NAME` 的请求头，缺失或格式错误时提取失败
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Header<T>(pub T);
+impl<T> Header<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<T> Deref for Header<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for Header<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+impl<T: TypedHeader> FromRequest for Header<T> {
+    type Error = ExtractError;
+    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+        let value = req.header(T::NAME).ok_or_else(|| ExtractError::Missing {
+            source: Source::Header,
+            name: T::NAME.to_string(),
+        })?;
+        T::decode(value)
+            .map(Header)
+            .map_err(|source| ExtractError::Header {
+                name: T::NAME,
+                source,
+            })
+    }
+}
+
+/// 全部请求头，保留原有顺序和重复的头
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Headers(Vec<(String, String)>);
+impl Headers {
+    /// 第一个名为 `name` 的头，忽略大小写
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+    /// 所有名为 `name` 的头，忽略大小写
+    pub fn get_all<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.iter()
+            .filter(move |(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+    /// 解析名为 `T::NAME` 的头，不存在时返回 `None`
+    pub fn typed<T: TypedHeader>(&self) -> Option<Result<T, ExtractError>> {
+        self.get(T::NAME).map(|value| {
+            T::decode(value).map_err(|source| ExtractError::Header {
+                name: T::NAME,
+                source,
+            })
+        })
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+impl FromRequest for Headers {
+    type Error = ExtractError;
+    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+        Ok(Self(
+            req.headers()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        ))
+    }
+}
+
+impl Response {
+    /// 以 `T::NAME` 为名设置强类型的响应头
+    pub fn with_typed_header<T: TypedHeader>(self, header: T) -> Self {
+        self.with_header(T::NAME, header.encode())
+    }
+}
+
+/// `Content-Type`，只做规范化，不解析参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentType(String);
+impl ContentType {
+    pub fn new(mime: &str) -> Self {
+        Self(mime.trim().to_string())
+    }
+    /// 不含参数的 MIME 类型，已转为小写
+    pub fn mime(&self) -> String {
+        let mime = self.0.split(';').next().unwrap_or("");
+        mime.trim().to_ascii_lowercase()
+    }
+    /// `;` 之后名为 `name` 的参数，如 `charset`
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.0
+            .split(';')
+            .skip(1)
+            .filter_map(|param| param.split_once('='))
+            .find(|(k, _)| k.trim().eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.trim().trim_matches('"'))
+    }
+}
+impl TypedHeader for ContentType {
+    const NAME: &'static str = "Content-Type";
+    fn decode(value: &str) -> Result<Self, BoxError> {
+        let mime = value.split(';').next().unwrap_or("").trim();
+        match mime.split_once('/') {
+            Some((ty, subtype)) if !ty.is_empty() && !subtype.is_empty() => Ok(Self::new(value)),
+            _ => Err(format!("invalid media type `{}`", value).into()),
+        }
+    }
+    fn encode(&self) -> String {
+        self.0.clone()
+    }
+}
+
+/// `Content-Length`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentLength(pub u64);
+impl TypedHeader for ContentLength {
+    const NAME: &'static str = "Content-Length";
+    fn decode(value: &str) -> Result<Self, BoxError> {
+        Ok(Self(value.trim().parse()?))
+    }
+    fn encode(&self) -> String {
+        self.0.to_string()
+    }
+}
+
+/// `Authorization: <scheme> <credentials>`
+///
+/// `Debug` 和 `Display` 都不输出凭据
+#[derive(Clone, PartialEq, Eq)]
+pub struct Authorization {
+    scheme: String,
+    credentials: String,
+}
+impl Authorization {
+    pub fn bearer(token: &str) -> Self {
+        Self {
+            scheme: "Bearer".to_string(),
+            credentials: token.to_string(),
+        }
+    }
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+    pub fn credentials(&self) -> &str {
+        &self.credentials
+    }
+    /// scheme 为 `Bearer` 时的令牌，忽略大小写
+    pub fn token(&self) -> Option<&str> {
+        if self.scheme.eq_ignore_ascii_case("Bearer") {
+            Some(&self.credentials)
+        } else {
+            None
+        }
+    }
+}
+impl TypedHeader for Authorization {
+    const NAME: &'static str = "Authorization";
+    fn decode(value: &str) -> Result<Self, BoxError> {
+        match value.trim().split_once(' ') {
+            Some((scheme, credentials)) if !credentials.trim().is_empty() => Ok(Self {
+                scheme: scheme.to_string(),
+                credentials: credentials.trim().to_string(),
+            }),
+            _ => Err("expected `<scheme> <credentials>`".into()),
+        }
+    }
+    fn encode(&self) -> String {
+        format!("{} {}", self.scheme, self.credentials)
+    }
+}
+impl fmt::Debug for Authorization {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Authorization")
+            .field("scheme", &self.scheme)
+            .field("credentials", &"***")
+            .finish()
+    }
+}
+impl fmt::Display for Authorization {
+    /// 不输出凭据，避免被写进日志
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ***", self.scheme)
+    }
+}
+
+/// `User-Agent`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserAgent(pub String);
+impl TypedHeader for UserAgent {
+    const NAME: &'static str = "User-Agent";
+    fn decode(value: &str) -> Result<Self, BoxError> {
+        Ok(Self(value.to_string()))
+    }
+    fn encode(&self) -> String {
+        self.0.clone()
+    }
+}
+
+#[test]
+fn test_typed_headers() {
+    let req = Request::new("")
+        .with_header("content-type", "text/HTML; charset=\"utf-8\"")
+        .with_header("Authorization", "Bearer abc.def")
+        .with_header("Content-Length", "12x")
+        .with_header("X-Tag", "a")
+        .with_header("x-tag", "b");
+
+    let Header(content_type) = Header::<ContentType>::from_request(&req).unwrap();
+    assert_eq!(content_type.mime(), "text/html");
+    assert_eq!(content_type.param("charset"), Some("utf-8"));
+    let Header(auth) = Header::<Authorization>::from_request(&req).unwrap();
+    assert_eq!(auth.token(), Some("abc.def"));
+    assert_eq!(auth.to_string(), "Bearer ***");
+    assert!(!format!("{:?}", Header(auth)).contains("abc"));
+
+    // 格式错误和缺失都会指明是哪个头
+    let err = Header::<ContentLength>::from_request(&req).unwrap_err();
+    assert!(matches!(
+        err,
+        ExtractError::Header {
+            name: "Content-Length",
+            ..
+        }
+    ));
+    let err = Header::<UserAgent>::from_request(&req).unwrap_err();
+    assert_eq!(err.to_string(), "missing header `User-Agent`");
+    assert!(Option::<Header<UserAgent>>::from_request(&req)
+        .unwrap()
+        .is_none());
+
+    let headers = Headers::from_request(&req).unwrap();
+    assert_eq!(headers.len(), 5);
+    assert_eq!(headers.get_all("X-TAG").collect::<Vec<_>>(), ["a", "b"]);
+    assert!(headers.typed::<ContentLength>().unwrap().is_err());
+    assert!(headers.typed::<UserAgent>().is_none());
+
+    let response = Response::ok().with_typed_header(Authorization::bearer("t"));
+    assert_eq!(response.header("authorization"), Some("Bearer t"));
+}
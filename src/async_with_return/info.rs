@@ -0,0 +1,133 @@
+use super::Request;
+
+/// handler 的元信息，注册后即使被擦除为 `Box<dyn Service>` 也能用于日志和查询
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandlerInfo {
+    name: &'static str,
+    /// 所在 scope 的路由前缀，如 `/api/v1`，不在 scope 中时为空
+    prefix: String,
+    route: Option<&'static str>,
+    method: Option<&'static str>,
+}
+impl HandlerInfo {
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            prefix: String::new(),
+            route: None,
+            method: None,
+        }
+    }
+    /// 只处理路径匹配 `route` 的请求，`:name` 匹配一段路径并作为路径参数
+    pub fn with_route(mut self, route: &'static str) -> Self {
+        self.route = Some(route);
+        self
+    }
+    /// 只处理方法为 `method` 的请求
+    pub fn with_method(mut self, method: &'static str) -> Self {
+        self.method = Some(method);
+        self
+    }
+
+    /// 在外层 scope 的前缀之下，见 `App::scope`
+    pub(super) fn with_prefix(mut self, prefix: &str) -> Self {
+        let prefix = prefix.trim_matches('/');
+        if !prefix.is_empty() {
+            self.prefix = format!("/{}{}", prefix, self.prefix);
+        }
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+    /// 所在 scope 的路由前缀，`route` 在它之下匹配
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+    pub fn route(&self) -> Option<&'static str> {
+        self.route
+    }
+    pub fn method(&self) -> Option<&'static str> {
+        self.method
+    }
+
+    /// 请求匹配时返回从路径中捕获的参数
+    ///
+    /// 路径先匹配 scope 的前缀，再匹配 `route`；没有 `route` 时处理前缀之下的所有请求
+    pub fn matches(&self, req: &Request) -> Option<Vec<(&str, String)>> {
+        if let Some(method) = self.method {
+            if !method.eq_ignore_ascii_case(req.method()) {
+                return None;
+            }
+        }
+        let mut params = vec![];
+        let mut segments = split(req.path());
+        for pattern in split(&self.prefix) {
+            match_segment(pattern, segments.next()?, &mut params)?;
+        }
+        let route = match self.route {
+            Some(route) => route,
+            None => return Some(params),
+        };
+        for pattern in split(route) {
+            match_segment(pattern, segments.next()?, &mut params)?;
+        }
+        match segments.next() {
+            Some(_) => None,
+            None => Some(params),
+        }
+    }
+}
+
+/// 忽略空的路径段，`/users/` 与 `/users` 相同
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|segment| !segment.is_empty())
+}
+
+/// `:name` 匹配任意一段并记下参数，其它必须相同
+fn match_segment<'p>(
+    pattern: &'p str,
+    segment: &str,
+    params: &mut Vec<(&'p str, String)>,
+) -> Option<()> {
+    match pattern.strip_prefix(':') {
+        Some(name) => params.push((name, segment.to_string())),
+        None if pattern == segment => {}
+        None => return None,
+    }
+    Some(())
+}
+
+#[test]
+fn test_route_matches() {
+    let info = HandlerInfo::new("get_user")
+        .with_route("/users/:id")
+        .with_method("GET");
+    assert_eq!(info.name(), "get_user");
+
+    let req = Request::new("").with_uri("/users/42?verbose=1");
+    assert_eq!(info.matches(&req), Some(vec![("id", "42".to_string())]));
+
+    assert_eq!(info.matches(&req.clone().with_method("post")), None);
+    assert_eq!(info.matches(&req.clone().with_uri("/users")), None);
+    assert_eq!(info.matches(&req.clone().with_uri("/users/42/posts")), None);
+
+    // 没有路由的 handler 处理所有请求
+    let anonymous = HandlerInfo::new("anonymous");
+    assert_eq!(anonymous.matches(&req), Some(vec![]));
+
+    // scope 的前缀同样可以捕获参数，嵌套的前缀依次拼接
+    let scoped = info.with_prefix("/v1/").with_prefix("/orgs/:org");
+    assert_eq!(scoped.prefix(), "/orgs/:org/v1");
+    let req = Request::new("").with_uri("/orgs/rust/v1/users/42/");
+    let params = scoped.matches(&req).unwrap();
+    assert_eq!(
+        params,
+        [("org", "rust".to_string()), ("id", "42".to_string())]
+    );
+    assert_eq!(scoped.matches(&req.clone().with_uri("/v1/users/42")), None);
+    let all = anonymous.with_prefix("/admin");
+    assert!(all.matches(&req.clone().with_uri("/admin/stats")).is_some());
+    assert_eq!(all.matches(&req.with_uri("/administrator")), None);
+}
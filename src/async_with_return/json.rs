@@ -0,0 +1,166 @@
+use std::{
+    any::type_name,
+    future,
+    ops::{Deref, DerefMut},
+};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{BoxError, ExtractError, ExtractFuture, FromRequestBody, Request, Responder, Response};
+
+/// `Json<T>` 提取器的配置，通过 `App::extractor_config` 设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonConfig {
+    /// 请求体的最大字节数
+    pub limit: usize,
+    /// 是否要求 `Content-Type` 为 `application/json` 或 `*/*+json`
+    pub content_type_required: bool,
+}
+impl Default for JsonConfig {
+    fn default() -> Self {
+        Self {
+            limit: 256 * 1024,
+            content_type_required: true,
+        }
+    }
+}
+
+/// 作为提取器时反序列化请求体，作为返回值时序列化为响应
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Json<T>(pub T);
+impl<T> Json<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<T> Deref for Json<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for Json<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+fn is_json(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or("").trim();
+    mime.eq_ignore_ascii_case("application/json")
+        || (mime.len() > 5 && mime[mime.len() - 5..].eq_ignore_ascii_case("+json"))
+}
+
+/// 将 serde_json 报告的行列号（均从 1 开始）换算为字节偏移
+fn byte_offset(input: &[u8], line: usize, column: usize) -> usize {
+    let line_start: usize = input
+        .split_inclusive(|&b| b == b'\n')
+        .take(line.saturating_sub(1))
+        .map(<[u8]>::len)
+        .sum();
+    (line_start + column.saturating_sub(1)).min(input.len())
+}
+
+impl<T> FromRequestBody for Json<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        Box::pin(future::ready(Self::extract(req)))
+    }
+}
+impl<T> Json<T>
+where
+    T: DeserializeOwned,
+{
+    /// 先检查请求头和大小，再取走请求体反序列化
+    fn extract(req: &mut Request) -> Result<Self, ExtractError> {
+        let default = JsonConfig::default();
+        let config = req.config::<JsonConfig>().unwrap_or(&default);
+
+        if config.content_type_required {
+            let content_type = req.header("Content-Type");
+            if !content_type.is_some_and(is_json) {
+                return Err(ExtractError::ContentType {
+                    expected: "application/json",
+                    found: content_type.map(str::to_string),
+                });
+            }
+        }
+        if req.body().len() > config.limit {
+            return Err(ExtractError::PayloadTooLarge {
+                size: req.body().len(),
+                limit: config.limit,
+            });
+        }
+        let body = req.take_body();
+        serde_json::from_slice(body.bytes())
+            .map(Json)
+            .map_err(|source| ExtractError::Json {
+                ty: type_name::<T>(),
+                offset: byte_offset(body.bytes(), source.line(), source.column()),
+                source,
+            })
+    }
+}
+
+impl<T> Responder for Json<T>
+where
+    T: Serialize,
+{
+    fn respond_to(self) -> Result<Response, BoxError> {
+        let body = serde_json::to_string(&self.0)?;
+        Ok(Response::ok()
+            .with_header("Content-Type", "application/json")
+            .with_body(body))
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_json() {
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, Serialize, PartialEq)]
+    struct User {
+        name: String,
+        age: u8,
+    }
+
+    let mut req = Request::new(r#"{"name": "alice", "age": 18}"#)
+        .with_header("Content-Type", "application/json; charset=utf-8");
+    let Json(user) = Json::<User>::from_request_body(&mut req).await.unwrap();
+    assert_eq!(user.name, "alice");
+    // 请求体已经被取走
+    assert!(req.body().is_empty());
+
+    let response = Json(user).respond_to().unwrap();
+    assert_eq!(response.header("content-type"), Some("application/json"));
+    assert_eq!(response.body(), r#"{"name":"alice","age":18}"#);
+
+    // 错误指向发现错误时所在的字节
+    let body = "{\n  \"name\": \"bob\",\n  \"age\": 300\n}";
+    let mut req = Request::new(body).with_header("Content-Type", "application/problem+json");
+    match Json::<User>::from_request_body(&mut req).await.unwrap_err() {
+        ExtractError::Json { offset, .. } => assert!(body[..=offset].ends_with("300")),
+        e => panic!("unexpected error: {}", e),
+    }
+
+    let mut req = Request::new("{}").with_header("Content-Type", "text/plain");
+    let err = Json::<User>::from_request_body(&mut req).await.unwrap_err();
+    assert!(matches!(
+        err,
+        ExtractError::ContentType { found: Some(_), .. }
+    ));
+
+    let config = JsonConfig {
+        limit: 8,
+        content_type_required: false,
+    };
+    let mut req = Request::new(r#"{"name": "alice", "age": 18}"#).with_extension(config);
+    let err = Json::<User>::from_request_body(&mut req).await.unwrap_err();
+    assert!(matches!(
+        err,
+        ExtractError::PayloadTooLarge { size: 28, limit: 8 }
+    ));
+}
@@ -0,0 +1,286 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    hash::Hash,
+    marker::PhantomData,
+    pin::Pin,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use super::{DispatchError, FromRequestParts, Request, Service, ServiceFuture, Trace};
+
+/// 分组的键，提取失败的请求归入 `None` 一组；不分组时 `K` 为 `()`
+///
+/// 可以是任意只读取请求的提取器，包括需要等待的，例如从会话中加载的用户
+pub trait LimitKey: FromRequestParts + Hash + Eq + Clone + 'static {}
+impl<K: FromRequestParts + Hash + Eq + Clone + 'static> LimitKey for K {}
+
+async fn key_of<K: LimitKey>(req: &Request) -> Option<K> {
+    K::from_request_parts(req).await.ok()
+}
+
+/// 令牌桶限流，通过 `App::rate_limit` 设置给最近注册的 handler，
+/// 或通过 `App::shared_rate_limit` 由一组 handler 共享
+///
+/// 桶中最多有 `capacity` 个令牌，每 `per / capacity` 补充一个，每次调用消耗一个
+pub struct RateLimit<K = ()> {
+    capacity: u32,
+    per: Duration,
+    _key: PhantomData<fn() -> K>,
+}
+impl RateLimit {
+    pub fn new(capacity: u32, per: Duration) -> Self {
+        assert!(capacity > 0, "rate limit capacity must be positive");
+        Self {
+            capacity,
+            per,
+            _key: PhantomData,
+        }
+    }
+}
+impl<K> RateLimit<K> {
+    /// 按提取出的 `K` 分别限流，如客户端 id
+    pub fn key_by<K2: LimitKey>(self) -> RateLimit<K2> {
+        RateLimit {
+            capacity: self.capacity,
+            per: self.per,
+            _key: PhantomData,
+        }
+    }
+}
+impl<K> Clone for RateLimit<K> {
+    fn clone(&self) -> Self {
+        Self {
+            capacity: self.capacity,
+            per: self.per,
+            _key: PhantomData,
+        }
+    }
+}
+impl<K> fmt::Debug for RateLimit<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimit")
+            .field("capacity", &self.capacity)
+            .field("per", &self.per)
+            .finish()
+    }
+}
+
+/// 同时执行的调用数上限，超出时直接拒绝，通过 `App::concurrency_limit` 或
+/// `App::shared_concurrency_limit` 设置
+pub struct ConcurrencyLimit<K = ()> {
+    max: usize,
+    _key: PhantomData<fn() -> K>,
+}
+impl ConcurrencyLimit {
+    pub fn new(max: usize) -> Self {
+        assert!(max > 0, "concurrency limit must be positive");
+        Self {
+            max,
+            _key: PhantomData,
+        }
+    }
+}
+impl<K> ConcurrencyLimit<K> {
+    /// 按提取出的 `K` 分别计数
+    pub fn key_by<K2: LimitKey>(self) -> ConcurrencyLimit<K2> {
+        ConcurrencyLimit {
+            max: self.max,
+            _key: PhantomData,
+        }
+    }
+}
+impl<K> Clone for ConcurrencyLimit<K> {
+    fn clone(&self) -> Self {
+        Self {
+            max: self.max,
+            _key: PhantomData,
+        }
+    }
+}
+impl<K> fmt::Debug for ConcurrencyLimit<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConcurrencyLimit")
+            .field("max", &self.max)
+            .finish()
+    }
+}
+
+/// 取得名额时返回，调用结束或被放弃时丢弃以归还名额
+pub(super) struct Permit<'a>(Option<Box<dyn FnOnce() + 'a>>);
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if let Some(release) = self.0.take() {
+            release();
+        }
+    }
+}
+
+/// 提取键时借用请求，得到的 `Permit` 只借用限流器
+pub(super) type LimitFuture<'r, 'a> =
+    Pin<Box<dyn Future<Output = Result<Permit<'a>, DispatchError>> + 'r>>;
+
+/// 限流或并发上限的状态，可以由单个 handler 独占，也可以由一组 handler 共享
+pub(super) trait Limiter {
+    fn acquire<'r, 'a: 'r>(&'a self, req: &'r Request) -> LimitFuture<'r, 'a>;
+}
+
+struct Bucket {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+struct Buckets<K> {
+    buckets: HashMap<Option<K>, Bucket>,
+    /// 桶的数量达到该值时清理已经补满的桶
+    prune_at: usize,
+}
+
+const MIN_PRUNE: usize = 1024;
+
+pub(super) struct RateLimiter<K> {
+    limit: RateLimit<K>,
+    buckets: Mutex<Buckets<K>>,
+}
+impl<K: LimitKey> RateLimiter<K> {
+    pub(super) fn new(limit: RateLimit<K>) -> Self {
+        Self {
+            limit,
+            buckets: Mutex::new(Buckets {
+                buckets: HashMap::new(),
+                prune_at: MIN_PRUNE,
+            }),
+        }
+    }
+    /// 补充到现在为止的令牌
+    fn refill(&self, bucket: &mut Bucket, now: Instant) {
+        let capacity = f64::from(self.limit.capacity);
+        let elapsed = now.duration_since(bucket.refilled_at).as_secs_f64();
+        bucket.tokens =
+            capacity.min(bucket.tokens + elapsed * capacity / self.limit.per.as_secs_f64());
+        bucket.refilled_at = now;
+    }
+    fn take_token(&self, key: Option<K>) -> Result<(), DispatchError> {
+        let capacity = f64::from(self.limit.capacity);
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap();
+        // 补满的桶与新建的桶等价，可以丢弃，避免客户端控制的键无限增长
+        if buckets.buckets.len() >= buckets.prune_at {
+            buckets.buckets.retain(|_, bucket| {
+                self.refill(bucket, now);
+                bucket.tokens < capacity
+            });
+            buckets.prune_at = MIN_PRUNE.max(buckets.buckets.len() * 2);
+        }
+        let bucket = buckets.buckets.entry(key).or_insert_with(|| Bucket {
+            tokens: capacity,
+            refilled_at: now,
+        });
+        self.refill(bucket, now);
+        if bucket.tokens < 1.0 {
+            return Err(DispatchError::RateLimited);
+        }
+        bucket.tokens -= 1.0;
+        Ok(())
+    }
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.buckets.lock().unwrap().buckets.len()
+    }
+}
+impl<K: LimitKey> Limiter for RateLimiter<K> {
+    fn acquire<'r, 'a: 'r>(&'a self, req: &'r Request) -> LimitFuture<'r, 'a> {
+        Box::pin(async move {
+            self.take_token(key_of::<K>(req).await)?;
+            Ok(Permit(None))
+        })
+    }
+}
+
+pub(super) struct ConcurrencyLimiter<K> {
+    limit: ConcurrencyLimit<K>,
+    in_flight: Mutex<HashMap<Option<K>, usize>>,
+}
+impl<K: LimitKey> ConcurrencyLimiter<K> {
+    pub(super) fn new(limit: ConcurrencyLimit<K>) -> Self {
+        Self {
+            limit,
+            in_flight: Mutex::default(),
+        }
+    }
+    /// 计数归零时移除这个键
+    fn release(&self, key: &Option<K>) {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        if let Some(count) = in_flight.get_mut(key) {
+            *count -= 1;
+            if *count == 0 {
+                in_flight.remove(key);
+            }
+        }
+    }
+}
+impl<K: LimitKey> Limiter for ConcurrencyLimiter<K> {
+    fn acquire<'r, 'a: 'r>(&'a self, req: &'r Request) -> LimitFuture<'r, 'a> {
+        Box::pin(async move {
+            let key = key_of::<K>(req).await;
+            let mut in_flight = self.in_flight.lock().unwrap();
+            let count = in_flight.entry(key.clone()).or_insert(0);
+            if *count >= self.limit.max {
+                return Err(DispatchError::ConcurrencyLimited);
+            }
+            *count += 1;
+            Ok(Permit(Some(Box::new(move || self.release(&key)))))
+        })
+    }
+}
+
+/// 取得名额后才调用内部的 `Service`，用于 `App::rate_limit` 和 `App::concurrency_limit`
+pub(super) struct LimitService {
+    inner: Box<dyn Service>,
+    limiter: Box<dyn Limiter>,
+}
+impl LimitService {
+    pub(super) fn new(inner: Box<dyn Service>, limiter: Box<dyn Limiter>) -> Self {
+        Self { inner, limiter }
+    }
+}
+impl Service for LimitService {
+    fn handle_request<'a>(&'a self, req: Request, trace: &'a Trace) -> ServiceFuture<'a> {
+        Box::pin(async move {
+            let acquired = self.limiter.acquire(&req).await;
+            let _permit = acquired.map_err(|err| trace.rejected(err))?;
+            self.inner.handle_request(req, trace).await
+        })
+    }
+}
+
+/// 依次取得 handler 所在 scope 和 `App` 上共享的名额，再调用 handler 自己的 `Service`
+pub(super) struct SharedLimits<'s> {
+    pub(super) limiters: Vec<&'s dyn Limiter>,
+    pub(super) inner: &'s dyn Service,
+}
+impl Service for SharedLimits<'_> {
+    fn handle_request<'a>(&'a self, req: Request, trace: &'a Trace) -> ServiceFuture<'a> {
+        Box::pin(async move {
+            let mut permits = vec![];
+            for limiter in &self.limiters {
+                let acquired = limiter.acquire(&req).await;
+                permits.push(acquired.map_err(|err| trace.rejected(err))?);
+            }
+            self.inner.handle_request(req, trace).await
+        })
+    }
+}
+
+#[test]
+fn test_prune_buckets() {
+    let limit = RateLimit::new(1, Duration::from_nanos(1)).key_by::<usize>();
+    let limiter = RateLimiter::new(limit);
+    for n in 0..MIN_PRUNE * 4 {
+        limiter.take_token(Some(n)).unwrap();
+    }
+    // 每个桶都已经补满，清理后不会随键的数量增长
+    assert!(limiter.len() <= MIN_PRUNE);
+}
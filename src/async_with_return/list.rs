@@ -0,0 +1,234 @@
+use std::{
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+    str::{self, FromStr},
+};
+
+use super::{BoxError, ExtractError, ExtractFuture, FromRequestBody, Request, ViaBody};
+
+/// `List<T, Sep>` 的分隔方式
+pub trait Separator {
+    fn split(input: &str) -> Vec<&str>;
+}
+
+/// 按字符 `sep` 分隔，去掉每个元素两侧的空白；空白字符表示按连续的空白分隔
+fn split(input: &str, sep: char) -> Vec<&str> {
+    if sep.is_whitespace() {
+        input.split_whitespace().collect()
+    } else if input.trim().is_empty() {
+        vec![]
+    } else {
+        input.split(sep).map(str::trim).collect()
+    }
+}
+
+macro_rules! separator {
+    ($($(#[$meta:meta])* $name:ident => $sep:literal),* $(,)?) => {
+        $(
+            $(#[$meta])*
+            #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+            pub struct $name;
+            impl Separator for $name {
+                fn split(input: &str) -> Vec<&str> {
+                    split(input, $sep)
+                }
+            }
+        )*
+    };
+}
+separator! {
+    /// `1,2,3`
+    Comma => ',',
+    /// `1;2;3`
+    Semicolon => ';',
+    /// `1|2|3`
+    Pipe => '|',
+    /// `1 2  3`，连续的空白视为一个分隔符
+    Whitespace => ' ',
+}
+
+/// 取走请求体并按 `Sep` 分隔，每个元素单独提取为 `T`
+///
+/// 作为字段时也可以配合 `#[from_request(query)]` 使用，此时要求 `T: FromStr`
+pub struct List<T, Sep = Comma>(pub Vec<T>, PhantomData<Sep>);
+impl<T, Sep> List<T, Sep> {
+    pub fn new(items: Vec<T>) -> Self {
+        Self(items, PhantomData)
+    }
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+impl<T: fmt::Debug, Sep> fmt::Debug for List<T, Sep> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("List").field(&self.0).finish()
+    }
+}
+impl<T: Clone, Sep> Clone for List<T, Sep> {
+    fn clone(&self) -> Self {
+        Self::new(self.0.clone())
+    }
+}
+impl<T: PartialEq, Sep> PartialEq for List<T, Sep> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+impl<T, Sep> Deref for List<T, Sep> {
+    type Target = Vec<T>;
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+impl<T, Sep> DerefMut for List<T, Sep> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+
+/// 以每个元素作为请求体依次提取，失败时报告元素的下标
+///
+/// 请求体已经被取走，请求只复制一次，之后每个元素只替换请求体
+async fn extract_each<T, M>(req: &Request, items: Vec<&str>) -> Result<Vec<T>, ExtractError>
+where
+    T: FromRequestBody<M>,
+{
+    let mut item_req = req.clone();
+    let mut values = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        item_req.set_body(item.as_bytes());
+        match T::from_request_body(&mut item_req).await {
+            Ok(value) => values.push(value),
+            Err(e) => {
+                let source = Box::new(e);
+                return Err(ExtractError::Element { index, source });
+            }
+        }
+    }
+    Ok(values)
+}
+
+impl<T, Sep, M> FromRequestBody<ViaBody<M>> for List<T, Sep>
+where
+    T: FromRequestBody<M> + 'static,
+    Sep: Separator + 'static,
+{
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        Box::pin(async move {
+            let body = req.take_body();
+            let body = str::from_utf8(body.bytes()).map_err(ExtractError::parse::<Self>)?;
+            extract_each(req, Sep::split(body)).await.map(Self::new)
+        })
+    }
+}
+impl<T, Sep> FromStr for List<T, Sep>
+where
+    T: FromStr,
+    T::Err: Into<BoxError>,
+    Sep: Separator,
+{
+    type Err = ExtractError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Sep::split(s)
+            .into_iter()
+            .enumerate()
+            .map(|(index, item)| {
+                item.parse().map_err(|e| ExtractError::Element {
+                    index,
+                    source: Box::new(ExtractError::parse::<T>(e)),
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(Self::new)
+    }
+}
+
+/// `Split<T>` 提取时使用的分隔符，通过 `App::extractor_config` 设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListConfig {
+    /// 空白字符表示按连续的空白分隔，默认为 `,`
+    pub separator: char,
+}
+impl Default for ListConfig {
+    fn default() -> Self {
+        Self { separator: ',' }
+    }
+}
+
+/// 与 `List<T, Sep>` 相同，但分隔符在分发时从 `ListConfig` 读取
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Split<T>(pub Vec<T>);
+impl<T> Split<T> {
+    pub fn into_inner(self) -> Vec<T> {
+        self.0
+    }
+}
+impl<T> Deref for Split<T> {
+    type Target = Vec<T>;
+    fn deref(&self) -> &Vec<T> {
+        &self.0
+    }
+}
+impl<T> DerefMut for Split<T> {
+    fn deref_mut(&mut self) -> &mut Vec<T> {
+        &mut self.0
+    }
+}
+impl<T, M> FromRequestBody<ViaBody<M>> for Split<T>
+where
+    T: FromRequestBody<M> + 'static,
+{
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        let separator = req
+            .config::<ListConfig>()
+            .map_or(',', |config| config.separator);
+        Box::pin(async move {
+            let body = req.take_body();
+            let body = str::from_utf8(body.bytes()).map_err(ExtractError::parse::<Self>)?;
+            extract_each(req, split(body, separator)).await.map(Split)
+        })
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_list() {
+    use super::{Either, Parsed};
+
+    let mut req = Request::new("1, 2,3");
+    let List(ids, _) = List::<u32>::from_request_body(&mut req).await.unwrap();
+    assert_eq!(ids, [1, 2, 3]);
+    assert!(List::<u32>::from_request_body(&mut Request::new(" "))
+        .await
+        .unwrap()
+        .is_empty());
+
+    // 元素可以与其它组合子嵌套，也可以是取走请求体的提取器
+    let req = Request::new("7  alice\n8");
+    let items =
+        List::<Either<u32, Parsed<String>>, Whitespace>::from_request_body(&mut req.clone())
+            .await
+            .unwrap();
+    assert_eq!(items[1], Either::Right(Parsed("alice".to_string())));
+    let names = List::<String, Whitespace>::from_request_body(&mut req.clone())
+        .await
+        .unwrap();
+    assert_eq!(names.into_inner(), ["7", "alice", "8"]);
+
+    let err = List::<u32, Whitespace>::from_request_body(&mut req.clone())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ExtractError::Element { index: 1, .. }));
+    assert!(err.to_string().starts_with("element #1: "));
+
+    let req = Request::new("").with_uri("/?ids=4|5");
+    let ids = super::Source::Query
+        .extract::<List<u8, Pipe>>(&req, "ids")
+        .unwrap();
+    assert_eq!(ids.into_inner(), [4, 5]);
+
+    let mut req = Request::new("1;x").with_extension(ListConfig { separator: ';' });
+    let err = Split::<i32>::from_request_body(&mut req).await.unwrap_err();
+    assert!(matches!(err, ExtractError::Element { index: 1, .. }));
+}
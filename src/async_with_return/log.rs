@@ -0,0 +1,134 @@
+use std::{fmt, fmt::Write, time::Duration};
+
+use serde_json::json;
+
+use super::{DispatchReport, HandlerReport, Request, Response};
+
+/// 日志的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    /// 每次分发一行概要，每个 handler 缩进一行
+    Text,
+    /// 每次分发一个 JSON 对象，占一行
+    JsonLines,
+}
+
+/// 记录每次分发的日志，通过 `App::logger` 设置
+///
+/// handler 以 `HandlerInfo::name` 标识，即 `#[handler]` 注册的名字或函数的类型名
+pub struct Logger {
+    format: LogFormat,
+    sink: Box<dyn Fn(&str)>,
+}
+impl Logger {
+    /// 输出到标准错误
+    pub fn new(format: LogFormat) -> Self {
+        Self::with_sink(format, |line| eprintln!("{}", line))
+    }
+    /// 由 `sink` 处理每条日志，日志本身不带结尾的换行
+    pub fn with_sink<F: Fn(&str) + 'static>(format: LogFormat, sink: F) -> Self {
+        Self {
+            format,
+            sink: Box::new(sink),
+        }
+    }
+    pub(super) fn log(&self, req: &Request, report: &DispatchReport) {
+        let record = match self.format {
+            LogFormat::Text => text(req, report),
+            LogFormat::JsonLines => json_line(req, report),
+        };
+        (self.sink)(&record)
+    }
+}
+impl fmt::Debug for Logger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Logger")
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// 与 `outcome` 一致，记录 `App::on_error` 转换之前的状态码或错误，转换得到的状态码另记
+fn result_of(handler: &HandlerReport) -> String {
+    let mut result = match handler.error() {
+        Some(e) => format!("error={:?}", e.to_string()),
+        None => String::new(),
+    };
+    if let Ok(response) = handler.result() {
+        let key = if result.is_empty() {
+            "status"
+        } else {
+            " on_error"
+        };
+        write!(result, "{}={}", key, response.status()).unwrap();
+    }
+    result
+}
+
+/// 路径和查询串来自客户端，转义后输出，避免换行伪造日志
+fn text(req: &Request, report: &DispatchReport) -> String {
+    let target = match req.query() {
+        "" => req.path().to_string(),
+        query => format!("{}?{}", req.path(), query),
+    };
+    let mut record = format!(
+        "{} {:?} body={}B handlers={} elapsed={:.2}ms",
+        req.method(),
+        target,
+        req.body().len(),
+        report.handlers().len(),
+        millis(report.elapsed()),
+    );
+    for handler in report.handlers() {
+        write!(
+            record,
+            "\n  {} {} {} attempts={} elapsed={:.2}ms",
+            handler.info().name(),
+            handler.outcome().as_str(),
+            result_of(handler),
+            handler.attempts().len(),
+            millis(handler.elapsed()),
+        )
+        .unwrap();
+    }
+    record
+}
+
+fn json_line(req: &Request, report: &DispatchReport) -> String {
+    let handlers: Vec<_> = report
+        .handlers()
+        .iter()
+        .map(|handler| {
+            let error = handler.error().map(ToString::to_string);
+            let response = handler.result().as_ref().ok().map(Response::status);
+            // 失败时 `status` 为空，`on_error` 转换得到的状态码记在 `on_error_status`
+            let (status, on_error_status) = match error {
+                Some(_) => (None, response),
+                None => (response, None),
+            };
+            json!({
+                "name": handler.info().name(),
+                "route": handler.info().route(),
+                "outcome": handler.outcome().as_str(),
+                "status": status,
+                "error": error,
+                "on_error_status": on_error_status,
+                "attempts": handler.attempts().len(),
+                "elapsed_ms": millis(handler.elapsed()),
+            })
+        })
+        .collect();
+    json!({
+        "method": req.method(),
+        "path": req.path(),
+        "query": req.query(),
+        "body_len": req.body().len(),
+        "elapsed_ms": millis(report.elapsed()),
+        "handlers": handlers,
+    })
+    .to_string()
+}
@@ -0,0 +1,401 @@
+use std::{
+    future,
+    str::{self, Utf8Error},
+};
+
+use super::{Chunks, ExtractError, ExtractFuture, FromRequestBody, Request};
+
+/// `multipart/form-data` 提取器的大小限制，通过 `App::extractor_config` 设置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultipartConfig {
+    /// 单个部分的最大字节数
+    pub part_limit: usize,
+    /// 整个请求体的最大字节数
+    pub total_limit: usize,
+}
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        Self {
+            part_limit: 1024 * 1024,
+            total_limit: 8 * 1024 * 1024,
+        }
+    }
+}
+
+/// `multipart/form-data` 中的一个部分
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    headers: Vec<(String, String)>,
+    name: Option<String>,
+    filename: Option<String>,
+    data: Vec<u8>,
+}
+impl Part {
+    /// `Content-Disposition` 中的 `name`
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    /// `Content-Disposition` 中的 `filename`，只有文件才有
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+    pub fn content_type(&self) -> Option<&str> {
+        self.header("Content-Type")
+    }
+    /// 第一个名为 `name` 的头，忽略大小写
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+    pub fn text(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(&self.data)
+    }
+}
+
+fn malformed(reason: &'static str) -> ExtractError {
+    ExtractError::Multipart { reason }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// 从 `Content-Type` 中取出 boundary
+fn boundary(req: &Request) -> Result<String, ExtractError> {
+    let content_type = req.header("Content-Type");
+    let mut params = content_type.unwrap_or("").split(';').map(str::trim);
+    if !params
+        .next()
+        .is_some_and(|mime| mime.eq_ignore_ascii_case("multipart/form-data"))
+    {
+        return Err(ExtractError::ContentType {
+            expected: "multipart/form-data",
+            found: content_type.map(str::to_string),
+        });
+    }
+    params
+        .filter_map(|param| param.split_once('='))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, v)| v.trim().trim_matches('"').to_string())
+        .filter(|boundary| !boundary.is_empty())
+        .ok_or_else(|| malformed("missing boundary"))
+}
+
+/// 解析 `Content-Disposition: form-data; name="a"; filename="b"` 中的参数
+fn disposition_param(disposition: &str, key: &str) -> Option<String> {
+    disposition
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.split_once('='))
+        .find(|(k, _)| k.trim().eq_ignore_ascii_case(key))
+        .map(|(_, v)| v.trim().trim_matches('"').to_string())
+}
+
+/// 每次从请求体中读取的字节数
+const CHUNK_SIZE: usize = 8 * 1024;
+/// 单个部分的头的最大字节数
+const HEADERS_LIMIT: usize = 8 * 1024;
+
+/// 逐个解析部分的流式接口，每次调用 `next` 只解析下一个部分
+///
+/// 请求体按块读取，只缓冲还没有解析的数据：每个部分在读到下一个分隔符时返回，
+/// 超过 `part_limit` 时立即报错，不会先把整个请求体复制一遍
+#[derive(Debug)]
+pub struct MultipartStream {
+    chunks: Chunks,
+    /// 已经读取、还没有解析的数据
+    buf: Vec<u8>,
+    /// `\r\n--boundary`
+    delimiter: Vec<u8>,
+    part_limit: usize,
+    done: bool,
+}
+impl MultipartStream {
+    /// 解析下一个部分，出错后不再继续
+    pub fn next_part(&mut self) -> Option<Result<Part, ExtractError>> {
+        if self.done {
+            return None;
+        }
+        let result = self.parse_part();
+        if !matches!(result, Some(Ok(_))) {
+            self.done = true;
+        }
+        result
+    }
+
+    /// 读取下一块，请求体读完时返回 `false`
+    fn read_chunk(&mut self) -> bool {
+        match self.chunks.next() {
+            Some(chunk) => {
+                self.buf.extend_from_slice(&chunk);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 在 `buf[from..]` 中查找 `needle`，不够时继续读取，返回它在 `buf` 中的位置
+    ///
+    /// 前面的数据超过 `limit` 时不再读取，返回 `Err` 和已经读到的字节数；读完仍未找到时返回 `Ok(None)`
+    fn find_buffered(
+        &mut self,
+        needle: &[u8],
+        from: usize,
+        limit: usize,
+    ) -> Result<Option<usize>, usize> {
+        let mut start = from;
+        loop {
+            if let Some(i) = find(&self.buf[start..], needle) {
+                let len = start + i - from;
+                return if len > limit {
+                    Err(len)
+                } else {
+                    Ok(Some(start + i))
+                };
+            }
+            // 末尾不足一个 `needle` 的数据可能是它的前缀，下次从这里继续找
+            start = (self.buf.len() + 1).saturating_sub(needle.len()).max(from);
+            if start - from > limit {
+                return Err(start - from);
+            }
+            if !self.read_chunk() {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// 跳过第一个分隔符之前的内容，丢弃已经查找过的数据
+    fn skip_preamble(&mut self) -> Result<(), ExtractError> {
+        loop {
+            if let Some(i) = find(&self.buf, &self.delimiter) {
+                self.buf.drain(..i + self.delimiter.len());
+                return Ok(());
+            }
+            let searched = (self.buf.len() + 1).saturating_sub(self.delimiter.len());
+            self.buf.drain(..searched);
+            if !self.read_chunk() {
+                return Err(malformed("missing boundary"));
+            }
+        }
+    }
+
+    fn parse_part(&mut self) -> Option<Result<Part, ExtractError>> {
+        while self.buf.len() < 2 && self.read_chunk() {}
+        // 分隔符之后是 `--` 表示结束，否则是 CRLF 和这个部分的头
+        if self.buf.starts_with(b"--") {
+            return None;
+        }
+        if !self.buf.starts_with(b"\r\n") {
+            return Some(Err(malformed("expected CRLF after boundary")));
+        }
+        let header_end = match self.find_buffered(b"\r\n\r\n", 2, HEADERS_LIMIT) {
+            Ok(Some(i)) => i,
+            Ok(None) => return Some(Err(malformed("unterminated part headers"))),
+            Err(_) => return Some(Err(malformed("part headers are too large"))),
+        };
+        let headers = match str::from_utf8(&self.buf[2..header_end]) {
+            Ok(headers) => headers,
+            Err(_) => return Some(Err(malformed("part headers are not UTF-8"))),
+        };
+        let mut parsed = vec![];
+        for line in headers.split("\r\n") {
+            match line.split_once(':') {
+                Some((k, v)) => parsed.push((k.trim().to_string(), v.trim().to_string())),
+                None => return Some(Err(malformed("invalid part header"))),
+            }
+        }
+        self.buf.drain(..header_end + 4);
+
+        let disposition = parsed
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case("Content-Disposition"))
+            .map(|(_, v)| v.as_str());
+        let name = disposition.and_then(|d| disposition_param(d, "name"));
+        let delimiter = self.delimiter.clone();
+        let data_len = match self.find_buffered(&delimiter, 0, self.part_limit) {
+            Ok(Some(i)) => i,
+            Ok(None) => return Some(Err(malformed("missing closing boundary"))),
+            Err(size) => {
+                return Some(Err(ExtractError::PartTooLarge {
+                    name,
+                    size,
+                    limit: self.part_limit,
+                }))
+            }
+        };
+        let part = Part {
+            filename: disposition.and_then(|d| disposition_param(d, "filename")),
+            name,
+            headers: parsed,
+            data: self.buf[..data_len].to_vec(),
+        };
+        self.buf.drain(..data_len + delimiter.len());
+        Some(Ok(part))
+    }
+}
+impl Iterator for MultipartStream {
+    type Item = Result<Part, ExtractError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_part()
+    }
+}
+impl FromRequestBody for MultipartStream {
+    /// 只检查 `Content-Type`、总大小和第一个分隔符，各个部分在迭代时才读取和解析
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        Box::pin(future::ready(Self::extract(req)))
+    }
+}
+impl MultipartStream {
+    fn extract(req: &mut Request) -> Result<Self, ExtractError> {
+        let default = MultipartConfig::default();
+        let config = req.config::<MultipartConfig>().unwrap_or(&default);
+        let (part_limit, total_limit) = (config.part_limit, config.total_limit);
+        let boundary = boundary(req)?;
+        let body = req.take_body();
+        if body.len() > total_limit {
+            return Err(ExtractError::PayloadTooLarge {
+                size: body.len(),
+                limit: total_limit,
+            });
+        }
+        let mut stream = Self {
+            chunks: body.chunks(CHUNK_SIZE),
+            // 第一个分隔符前面没有 CRLF，统一补上以便用同一个分隔符查找
+            buf: b"\r\n".to_vec(),
+            delimiter: format!("\r\n--{}", boundary).into_bytes(),
+            part_limit,
+            done: false,
+        };
+        stream.skip_preamble()?;
+        Ok(stream)
+    }
+}
+
+/// 一次性解析出所有部分的缓冲接口
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multipart {
+    parts: Vec<Part>,
+}
+impl Multipart {
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+    /// 第一个名为 `name` 的部分
+    pub fn get(&self, name: &str) -> Option<&Part> {
+        self.parts.iter().find(|part| part.name() == Some(name))
+    }
+    /// 所有带有文件名的部分
+    pub fn files(&self) -> impl Iterator<Item = &Part> {
+        self.parts.iter().filter(|part| part.filename().is_some())
+    }
+}
+impl IntoIterator for Multipart {
+    type Item = Part;
+    type IntoIter = std::vec::IntoIter<Part>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.parts.into_iter()
+    }
+}
+impl FromRequestBody for Multipart {
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        let parts = MultipartStream::extract(req).and_then(Iterator::collect);
+        Box::pin(future::ready(parts.map(|parts| Self { parts })))
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_multipart() {
+    let body = concat!(
+        "preamble\r\n",
+        "--XyZ\r\n",
+        "Content-Disposition: form-data; name=\"title\"\r\n",
+        "\r\n",
+        "hello\r\n",
+        "--XyZ\r\n",
+        "Content-Disposition: form-data; name=\"file\"; filename=\"a.bin\"\r\n",
+        "Content-Type: application/octet-stream\r\n",
+        "\r\n",
+        "\x00\x01\r\n--X\r\n",
+        "--XyZ--\r\n",
+    );
+    let req =
+        Request::new(body).with_header("Content-Type", "multipart/form-data; boundary=\"XyZ\"");
+
+    let multipart = Multipart::from_request_body(&mut req.clone())
+        .await
+        .unwrap();
+    assert_eq!(multipart.parts().len(), 2);
+    assert_eq!(multipart.get("title").unwrap().text().unwrap(), "hello");
+    let file = multipart.files().next().unwrap();
+    assert_eq!(file.filename(), Some("a.bin"));
+    assert_eq!(file.content_type(), Some("application/octet-stream"));
+    assert_eq!(file.data(), b"\x00\x01\r\n--X");
+
+    // 流式接口逐个返回部分，超过单个部分的限制时报错
+    let config = MultipartConfig {
+        part_limit: 5,
+        ..Default::default()
+    };
+    let mut stream = MultipartStream::from_request_body(&mut req.clone().with_extension(config))
+        .await
+        .unwrap();
+    assert_eq!(stream.next().unwrap().unwrap().name(), Some("title"));
+    match stream.next().unwrap().unwrap_err() {
+        ExtractError::PartTooLarge { name, size, limit } => {
+            assert_eq!((name.as_deref(), size, limit), (Some("file"), 7, 5))
+        }
+        e => panic!("unexpected error: {}", e),
+    }
+    assert!(stream.next().is_none());
+
+    let config = MultipartConfig {
+        total_limit: 16,
+        ..Default::default()
+    };
+    let err = Multipart::from_request_body(&mut req.clone().with_extension(config))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        ExtractError::PayloadTooLarge { limit: 16, .. }
+    ));
+
+    // 跨越多个块的部分也能完整读出，超过限制时不必读完整个部分就会报错
+    let big = "a".repeat(CHUNK_SIZE * 2 + 3);
+    let body = format!(
+        "{}\r\n--XyZ\r\nContent-Disposition: form-data; name=\"big\"\r\n\r\n{}\r\n--XyZ--",
+        big, big
+    );
+    let req = Request::new(body).with_header("Content-Type", "multipart/form-data; boundary=XyZ");
+    let multipart = Multipart::from_request_body(&mut req.clone())
+        .await
+        .unwrap();
+    assert_eq!(multipart.get("big").unwrap().data().len(), big.len());
+    let config = MultipartConfig {
+        part_limit: 100,
+        ..Default::default()
+    };
+    match Multipart::from_request_body(&mut req.with_extension(config))
+        .await
+        .unwrap_err()
+    {
+        ExtractError::PartTooLarge { size, .. } => assert!(size > 100 && size < big.len()),
+        e => panic!("unexpected error: {}", e),
+    }
+
+    let mut truncated =
+        Request::new("--XyZ\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\nabc")
+            .with_header("Content-Type", "multipart/form-data; boundary=XyZ");
+    let err = Multipart::from_request_body(&mut truncated)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ExtractError::Multipart { .. }));
+}
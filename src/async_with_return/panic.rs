@@ -0,0 +1,55 @@
+use std::{
+    any::Any,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// handler panic 时的处理方式，通过 `App::panic_policy` 设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// 转换为 `DispatchError::Panic`，继续执行后续的 handler，默认
+    #[default]
+    Isolate,
+    /// 不捕获，直接向 `App::dispatch` 的调用方传播，便于在测试中定位
+    Propagate,
+}
+
+/// panic 的参数通常是 `&str` 或 `String`
+pub(crate) fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(s) => *s,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(s) => s.to_string(),
+            Err(_) => "Box<dyn Any>".to_string(),
+        },
+    }
+}
+
+/// 在每次 `poll` 时捕获 panic 的 future，相当于异步版本的 `catch_unwind`
+///
+/// panic 之后内部的 future 不应再被 poll，返回 `Err` 后就不要继续使用了
+pub struct CatchUnwind<F>(F);
+impl<F> CatchUnwind<F>
+where
+    F: Future + Unpin,
+{
+    pub fn new(future: F) -> Self {
+        Self(future)
+    }
+}
+impl<F> Future for CatchUnwind<F>
+where
+    F: Future + Unpin,
+{
+    type Output = Result<F::Output, String>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let inner = &mut self.0;
+        match panic::catch_unwind(AssertUnwindSafe(|| Pin::new(inner).poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(payload) => Poll::Ready(Err(panic_message(payload))),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::ops::{Deref, DerefMut};
+
+use serde::de::DeserializeOwned;
+
+use super::{urlencoded, ExtractError, FromRequest, Request};
+
+/// 将查询字符串解码并反序列化为 `T`，见 [`urlencoded::from_str`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query<T>(pub T);
+impl<T> Query<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<T> Deref for Query<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for Query<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+impl<T> FromRequest for Query<T>
+where
+    T: DeserializeOwned,
+{
+    type Error = ExtractError;
+    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+        urlencoded::from_str(req.query())
+            .map(Query)
+            .map_err(ExtractError::parse::<T>)
+    }
+}
+
+#[test]
+fn test_query() {
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Debug, Deserialize)]
+    struct Filter {
+        limit: u32,
+        tag: Vec<String>,
+    }
+
+    let req = Request::new("").with_uri("/posts?limit=10&tag=a&tag=b%2Bc");
+    let Query(filter) = Query::<Filter>::from_request(&req).unwrap();
+    assert_eq!(filter.limit, 10);
+    assert_eq!(filter.tag, ["a", "b+c"]);
+
+    let Query(map) = Query::<HashMap<String, String>>::from_request(&req).unwrap();
+    assert_eq!(map["tag"], "a");
+    // 查询字符串的值同样会被解码
+    assert_eq!(req.query_param("tag").as_deref(), Some("a"));
+
+    let req = Request::new("").with_uri("/posts?limit=-1");
+    let err = Query::<Filter>::from_request(&req).unwrap_err();
+    assert!(matches!(err, ExtractError::Parse { .. }));
+}
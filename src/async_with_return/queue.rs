@@ -0,0 +1,347 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+    future::Future,
+    panic::{self, AssertUnwindSafe},
+    pin::Pin,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use super::{panic::panic_message, App, CatchUnwind, DispatchError, Request, Response};
+
+type Results = Vec<Result<Response, DispatchError>>;
+
+/// 队列已满时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Overflow {
+    /// 拒绝新的请求，返回 `QueueError::Full`
+    RejectNewest,
+    /// 丢弃最早排队的请求，它得到 `QueueError::Dropped`
+    DropOldest,
+    /// 等待队列中有空位
+    Block,
+}
+
+/// 请求没有被分发的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueueError {
+    Full,
+    Dropped,
+    /// 队列已关闭
+    Closed,
+    /// `PanicPolicy::Propagate` 时 handler 的 panic
+    Panicked(String),
+}
+impl fmt::Display for QueueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Full => write!(f, "dispatch queue is full"),
+            Self::Dropped => write!(f, "request dropped from dispatch queue"),
+            Self::Closed => write!(f, "dispatch queue is closed"),
+            Self::Panicked(message) => write!(f, "dispatch panicked: {}", message),
+        }
+    }
+}
+impl Error for QueueError {}
+
+/// 队列的统计数据，见 `DispatchQueue::metrics`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueueMetrics {
+    /// 当前排队等待的请求数
+    pub depth: usize,
+    /// 当前正在分发的请求数
+    pub in_flight: usize,
+    pub capacity: usize,
+    /// 出现过的最大 `depth`
+    pub max_depth: usize,
+    /// 进入过队列的请求数，不包括直接取得名额的
+    pub enqueued: u64,
+    pub rejected: u64,
+    pub dropped: u64,
+    pub completed: u64,
+}
+
+/// 排队等待分发的调用者
+struct Waiter {
+    id: u64,
+    /// 同步调用者没有 waker，通过 `DispatchQueue::changed` 唤醒
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct State {
+    queue: VecDeque<Waiter>,
+    /// 已经决定了结果、还没有被调用者取走的排队者
+    decided: HashMap<u64, Result<(), QueueError>>,
+    /// `Overflow::Block` 时异步等待空位的调用者
+    blocked: Vec<(u64, Waker)>,
+    next_id: u64,
+    closed: bool,
+    metrics: QueueMetrics,
+}
+impl State {
+    fn update_depth(&mut self) {
+        self.metrics.depth = self.queue.len();
+        self.metrics.max_depth = self.metrics.max_depth.max(self.metrics.depth);
+    }
+}
+
+/// 一个调用者在入队流程中的位置
+#[derive(Debug, Clone, Copy)]
+enum Stage {
+    New,
+    /// 队列已满，等待空位
+    Blocked(u64),
+    Queued(u64),
+    Done,
+}
+
+/// 在 `App::dispatch` 前面加一个有界队列，同时最多分发 `concurrency` 个请求，其余的排队
+///
+/// 队列只负责准入，分发在调用者自己的执行器上进行：`dispatch` 在调用它的异步任务中等待
+/// `App::dispatch`，`dispatch_blocking` 在当前线程上运行。队列本身可以在线程间共享，
+/// 每个线程使用自己的 `App`
+pub struct DispatchQueue {
+    overflow: Overflow,
+    concurrency: usize,
+    state: Mutex<State>,
+    /// 队列有变化，同步调用者重新检查
+    changed: Condvar,
+}
+impl DispatchQueue {
+    /// 最多 `capacity` 个请求排队，默认同时只分发一个
+    pub fn new(capacity: usize, overflow: Overflow) -> Self {
+        assert!(capacity > 0, "queue capacity must be positive");
+        let mut state = State::default();
+        state.metrics.capacity = capacity;
+        Self {
+            overflow,
+            concurrency: 1,
+            state: Mutex::new(state),
+            changed: Condvar::new(),
+        }
+    }
+    /// 同时分发的请求数上限
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        assert!(concurrency > 0, "queue concurrency must be positive");
+        self.concurrency = concurrency;
+        self
+    }
+    /// 排队等到名额后在当前任务中由 `app` 分发，`Overflow::Block` 时异步等待空位
+    pub async fn dispatch(&self, app: &App, req: Request) -> Result<Results, QueueError> {
+        let _admission = Admit {
+            queue: self,
+            stage: Stage::New,
+        }
+        .await?;
+        let results = CatchUnwind::new(Box::pin(app.dispatch(req)))
+            .await
+            .map_err(QueueError::Panicked);
+        self.lock().metrics.completed += 1;
+        results
+    }
+    /// 在同步代码中排队并在当前线程上分发，`Overflow::Block` 时阻塞等待空位
+    ///
+    /// 当前线程没有异步运行时，handler 只能等待本 crate 的 `sleep`、`timeout` 这类不依赖运行时的 future
+    pub fn dispatch_blocking(&self, app: &App, req: Request) -> Result<Results, QueueError> {
+        let _admission = self.admit_blocking()?;
+        let results = panic::catch_unwind(AssertUnwindSafe(|| block_on(app.dispatch(req))))
+            .map_err(|payload| QueueError::Panicked(panic_message(payload)));
+        self.lock().metrics.completed += 1;
+        results
+    }
+    pub fn metrics(&self) -> QueueMetrics {
+        self.lock().metrics.clone()
+    }
+    /// 之后的请求返回 `QueueError::Closed`，已经排队的请求仍会被分发
+    pub fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        for (_, waker) in state.blocked.drain(..) {
+            waker.wake();
+        }
+        self.changed.notify_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+    fn admit_blocking(&self) -> Result<Admission<'_>, QueueError> {
+        let mut stage = Stage::New;
+        let mut state = self.lock();
+        loop {
+            if let Some(result) = self.advance(&mut state, &mut stage, None) {
+                return result.map(|()| Admission(self));
+            }
+            state = self.changed.wait(state).unwrap();
+        }
+    }
+    /// 推进入队流程，返回 `Some` 时结束等待，`Ok` 表示取得了名额
+    fn advance(
+        &self,
+        state: &mut State,
+        stage: &mut Stage,
+        waker: Option<&Waker>,
+    ) -> Option<Result<(), QueueError>> {
+        let id = match *stage {
+            Stage::Done => panic!("polled after completion"),
+            Stage::Queued(id) => {
+                if let Some(result) = state.decided.remove(&id) {
+                    *stage = Stage::Done;
+                    return Some(result);
+                }
+                if let Some(waiter) = state.queue.iter_mut().find(|w| w.id == id) {
+                    waiter.waker = waker.cloned();
+                }
+                return None;
+            }
+            Stage::Blocked(id) => {
+                state.blocked.retain(|(blocked, _)| *blocked != id);
+                id
+            }
+            Stage::New => {
+                state.next_id += 1;
+                state.next_id
+            }
+        };
+        if state.closed {
+            *stage = Stage::Done;
+            return Some(Err(QueueError::Closed));
+        }
+        if state.queue.is_empty() && state.metrics.in_flight < self.concurrency {
+            state.metrics.in_flight += 1;
+            *stage = Stage::Done;
+            return Some(Ok(()));
+        }
+        if state.queue.len() >= state.metrics.capacity {
+            match self.overflow {
+                Overflow::RejectNewest => {
+                    state.metrics.rejected += 1;
+                    *stage = Stage::Done;
+                    return Some(Err(QueueError::Full));
+                }
+                Overflow::DropOldest => {
+                    if let Some(oldest) = state.queue.pop_front() {
+                        state.metrics.dropped += 1;
+                        state.decided.insert(oldest.id, Err(QueueError::Dropped));
+                        if let Some(waker) = oldest.waker {
+                            waker.wake();
+                        }
+                        self.changed.notify_all();
+                    }
+                }
+                Overflow::Block => {
+                    if let Some(waker) = waker {
+                        state.blocked.push((id, waker.clone()));
+                    }
+                    *stage = Stage::Blocked(id);
+                    return None;
+                }
+            }
+        }
+        state.queue.push_back(Waiter {
+            id,
+            waker: waker.cloned(),
+        });
+        state.metrics.enqueued += 1;
+        state.update_depth();
+        *stage = Stage::Queued(id);
+        None
+    }
+    /// 归还名额，按顺序交给排队的请求，并唤醒等待空位的调用者
+    fn release(&self, state: &mut State) {
+        state.metrics.in_flight -= 1;
+        while state.metrics.in_flight < self.concurrency {
+            let waiter = match state.queue.pop_front() {
+                Some(waiter) => waiter,
+                None => break,
+            };
+            state.metrics.in_flight += 1;
+            state.decided.insert(waiter.id, Ok(()));
+            if let Some(waker) = waiter.waker {
+                waker.wake();
+            }
+        }
+        state.update_depth();
+        for (_, waker) in state.blocked.drain(..) {
+            waker.wake();
+        }
+        self.changed.notify_all();
+    }
+}
+impl fmt::Debug for DispatchQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DispatchQueue")
+            .field("overflow", &self.overflow)
+            .field("concurrency", &self.concurrency)
+            .field("metrics", &self.metrics())
+            .finish()
+    }
+}
+
+/// 分发的名额，分发结束或被放弃时归还
+struct Admission<'a>(&'a DispatchQueue);
+impl Drop for Admission<'_> {
+    fn drop(&mut self) {
+        let queue = self.0;
+        queue.release(&mut queue.lock());
+    }
+}
+
+/// 异步等待名额，见 `DispatchQueue::dispatch`
+struct Admit<'a> {
+    queue: &'a DispatchQueue,
+    stage: Stage,
+}
+impl<'a> Future for Admit<'a> {
+    type Output = Result<Admission<'a>, QueueError>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let queue = self.queue;
+        let mut state = queue.lock();
+        match queue.advance(&mut state, &mut self.stage, Some(cx.waker())) {
+            Some(result) => Poll::Ready(result.map(|()| Admission(queue))),
+            None => Poll::Pending,
+        }
+    }
+}
+/// 放弃等待时移除留下的 waker 和排队位置；已经分到的名额交给下一个请求
+impl Drop for Admit<'_> {
+    fn drop(&mut self) {
+        let queue = self.queue;
+        let mut state = queue.lock();
+        match self.stage {
+            Stage::Blocked(id) => state.blocked.retain(|(blocked, _)| *blocked != id),
+            Stage::Queued(id) => match state.decided.remove(&id) {
+                Some(Ok(())) => queue.release(&mut state),
+                Some(Err(_)) => {}
+                None => {
+                    state.queue.retain(|waiter| waiter.id != id);
+                    state.update_depth();
+                }
+            },
+            Stage::New | Stage::Done => {}
+        }
+    }
+}
+
+/// 在当前线程上运行 future，等待时挂起线程
+fn block_on<F: Future>(future: F) -> F::Output {
+    struct Unpark(Thread);
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+    let waker = Waker::from(Arc::new(Unpark(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = Box::pin(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
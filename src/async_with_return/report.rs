@@ -0,0 +1,203 @@
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    time::Duration,
+};
+
+use super::{DispatchError, HandlerInfo, Response};
+
+/// 一次分发的完整记录，`App::dispatch` 只保留其中每个 handler 的结果
+#[derive(Debug)]
+pub struct DispatchReport {
+    handlers: Vec<HandlerReport>,
+    elapsed: Duration,
+}
+impl DispatchReport {
+    pub(super) fn new(handlers: Vec<HandlerReport>, elapsed: Duration) -> Self {
+        Self { handlers, elapsed }
+    }
+    /// 按注册顺序排列，不包括没有匹配请求的 handler
+    pub fn handlers(&self) -> &[HandlerReport] {
+        &self.handlers
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    pub fn into_results(self) -> Vec<Result<Response, DispatchError>> {
+        self.handlers.into_iter().map(|h| h.result).collect()
+    }
+}
+
+/// 一个 handler 的处理过程
+#[derive(Debug)]
+pub struct HandlerReport {
+    info: HandlerInfo,
+    outcome: Outcome,
+    result: Result<Response, DispatchError>,
+    /// 被 `App::on_error` 转换为响应的错误
+    handled: Option<DispatchError>,
+    attempts: Vec<Attempt>,
+    elapsed: Duration,
+}
+impl HandlerReport {
+    pub(super) fn new(
+        info: HandlerInfo,
+        outcome: Outcome,
+        result: Result<Response, DispatchError>,
+        handled: Option<DispatchError>,
+        trace: Trace,
+        elapsed: Duration,
+    ) -> Self {
+        Self {
+            info,
+            outcome,
+            result,
+            handled,
+            attempts: trace.attempts.into_inner(),
+            elapsed,
+        }
+    }
+    pub fn info(&self) -> &HandlerInfo {
+        &self.info
+    }
+    /// 经过 `App::on_error` 转换之前的结果
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+    /// 最终的结果，已经过 `App::on_error` 转换
+    pub fn result(&self) -> &Result<Response, DispatchError> {
+        &self.result
+    }
+    /// 失败时的错误，包括被 `App::on_error` 转换为响应的
+    pub fn error(&self) -> Option<&DispatchError> {
+        self.handled.as_ref().or(self.result.as_ref().err())
+    }
+    /// 每次执行完的尝试，包括产生最终结果的那一次
+    ///
+    /// 被熔断或限流拒绝、超时和 panic 的尝试同样记录在这里
+    pub fn attempts(&self) -> &[Attempt] {
+        &self.attempts
+    }
+    /// 包括重试和等待的总耗时
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+}
+
+/// handler 的处理结果分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Responded,
+    ExtractFailed,
+    HandlerFailed,
+    Panicked,
+    TimedOut,
+    /// 被熔断或限流拒绝
+    Rejected,
+}
+impl Outcome {
+    pub fn of(result: &Result<Response, DispatchError>) -> Self {
+        match result {
+            Ok(_) => Self::Responded,
+            Err(DispatchError::Extract(_)) => Self::ExtractFailed,
+            Err(DispatchError::Handler(_)) => Self::HandlerFailed,
+            Err(DispatchError::Panic(_)) => Self::Panicked,
+            Err(DispatchError::Timeout(_)) => Self::TimedOut,
+            Err(_) => Self::Rejected,
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Responded => "ok",
+            Self::ExtractFailed => "extract_failed",
+            Self::HandlerFailed => "handler_failed",
+            Self::Panicked => "panicked",
+            Self::TimedOut => "timed_out",
+            Self::Rejected => "rejected",
+        }
+    }
+}
+
+/// 一次提取和调用 handler 的尝试
+#[derive(Debug, Clone)]
+pub struct Attempt {
+    outcome: Outcome,
+    error: Option<String>,
+    elapsed: Duration,
+    delay: Option<Duration>,
+}
+impl Attempt {
+    pub fn outcome(&self) -> Outcome {
+        self.outcome
+    }
+    /// 失败时错误的描述
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+    /// 之后被重试时，重试前等待的时间
+    pub fn delay(&self) -> Option<Duration> {
+        self.delay
+    }
+}
+
+/// 分发时交给 `Service` 的记录器，中间件通过它把过程写入 `HandlerReport`
+#[derive(Default)]
+pub(super) struct Trace {
+    attempts: RefCell<Vec<Attempt>>,
+    /// 每次尝试的超时
+    timeout: Option<Duration>,
+    /// 由 `RetryPolicy::reuse_params` 开启，保存第一次提取的参数
+    reuse_params: Cell<bool>,
+    params: RefCell<Option<Box<dyn Any>>>,
+}
+impl Trace {
+    pub(super) fn new(timeout: Option<Duration>) -> Self {
+        Self {
+            timeout,
+            ..Self::default()
+        }
+    }
+    pub(super) fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+    pub(super) fn reuse_params(&self) {
+        self.reuse_params.set(true);
+    }
+    /// 开启复用时保存参数的副本，供之后的尝试使用
+    pub(super) fn cache_params<T: 'static>(&self, params: impl FnOnce() -> T) {
+        if self.reuse_params.get() {
+            *self.params.borrow_mut() = Some(Box::new(params()));
+        }
+    }
+    pub(super) fn cached_params<T: 'static>(&self) -> Option<T> {
+        let params = self.params.borrow_mut().take()?;
+        params.downcast().ok().map(|params| *params)
+    }
+    pub(super) fn attempt(&self, result: &Result<Response, DispatchError>, elapsed: Duration) {
+        self.attempts.borrow_mut().push(Attempt {
+            outcome: Outcome::of(result),
+            error: result.as_ref().err().map(ToString::to_string),
+            elapsed,
+            delay: None,
+        });
+    }
+    /// 记录没有调用 handler 就被拒绝的一次尝试
+    pub(super) fn rejected(&self, error: DispatchError) -> DispatchError {
+        self.attempts.borrow_mut().push(Attempt {
+            outcome: Outcome::Rejected,
+            error: Some(error.to_string()),
+            elapsed: Duration::ZERO,
+            delay: None,
+        });
+        error
+    }
+    /// 最近一次尝试之后将在 `delay` 后重试
+    pub(super) fn retry_after(&self, delay: Duration) {
+        if let Some(attempt) = self.attempts.borrow_mut().last_mut() {
+            attempt.delay = Some(delay);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    any::{Any, TypeId},
+    borrow::Cow,
+    collections::HashMap,
+    fmt, mem,
+    str::{self, Utf8Error},
+    sync::Arc,
+};
+
+use super::{urlencoded, AppContext, Body};
+
+/// 按类型存放的附加数据，`App` 在分发时通过它把配置交给提取器
+#[derive(Clone, Default)]
+pub struct Extensions(HashMap<TypeId, Arc<dyn Any + Send + Sync>>);
+impl Extensions {
+    pub fn insert<T: Send + Sync + 'static>(&mut self, value: T) {
+        self.0.insert(TypeId::of::<T>(), Arc::new(value));
+    }
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.0
+            .get(&TypeId::of::<T>())
+            .and_then(|value| value.downcast_ref())
+    }
+    pub fn extend(&mut self, other: Extensions) {
+        self.0.extend(other.0);
+    }
+}
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Extensions")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
+
+/// 结构化的请求，取代原先只有一个字符串的 `Request { s }`
+#[derive(Debug, Clone)]
+pub struct Request {
+    method: String,
+    path: String,
+    query: String,
+    headers: Vec<(String, String)>,
+    params: HashMap<String, String>,
+    body: Arc<[u8]>,
+    extensions: Extensions,
+    context: Arc<AppContext>,
+    /// handler 所在 scope 的上下文，从内到外，优先于 `context`
+    scopes: Vec<Arc<AppContext>>,
+}
+impl Request {
+    /// 以 `body` 作为请求体构造 `GET /` 请求
+    pub fn new(body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            method: "GET".to_string(),
+            path: "/".to_string(),
+            query: String::new(),
+            headers: vec![],
+            params: HashMap::new(),
+            body: body.into().into(),
+            extensions: Extensions::default(),
+            context: Arc::default(),
+            scopes: vec![],
+        }
+    }
+    pub fn with_method(mut self, method: impl Into<String>) -> Self {
+        self.method = method.into();
+        self
+    }
+    /// 设置路径，`?` 之后的部分作为查询字符串
+    pub fn with_uri(mut self, uri: &str) -> Self {
+        let (path, query) = match uri.find('?') {
+            Some(i) => (&uri[..i], &uri[i + 1..]),
+            None => (uri, ""),
+        };
+        self.path = path.to_string();
+        self.query = query.to_string();
+        self
+    }
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+    /// 设置路径参数，如 `/users/:id` 中的 `id`
+    pub fn with_param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.params.insert(name.into(), value.into());
+        self
+    }
+
+    pub fn with_body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into().into();
+        self
+    }
+
+    /// 只替换请求体，用于对同一个请求反复提取
+    pub(super) fn set_body(&mut self, body: &[u8]) {
+        self.body = body.into();
+    }
+
+    pub fn with_extension<T: Send + Sync + 'static>(mut self, value: T) -> Self {
+        self.extensions.insert(value);
+        self
+    }
+
+    pub fn method(&self) -> &str {
+        &self.method
+    }
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+    /// 未经解码的查询字符串
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+    /// 查询字符串中第一个名为 `name` 的参数，已解码
+    pub fn query_param(&self, name: &str) -> Option<Cow<'_, str>> {
+        urlencoded::parse(&self.query).find_map(|(k, v)| if k == name { Some(v) } else { None })
+    }
+    /// 第一个名为 `name` 的请求头，忽略大小写
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    pub fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params.get(name).map(String::as_str)
+    }
+    pub fn body(&self) -> &[u8] {
+        &self.body
+    }
+    /// 取走请求体，之后请求体为空；读取请求体的提取器通过它保证请求体只被消费一次
+    pub fn take_body(&mut self) -> Body {
+        Body::new(mem::replace(&mut self.body, Arc::from(&[][..])))
+    }
+    /// 放回取走的请求体，用于依次尝试多个取走请求体的提取器
+    pub(super) fn put_body(&mut self, body: &Body) {
+        self.body = body.share();
+    }
+    pub fn body_str(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(&self.body)
+    }
+    /// 分发这个请求的 `App` 的上下文，未经分发时为空
+    pub fn app_context(&self) -> &AppContext {
+        &self.context
+    }
+    pub(super) fn set_context(&mut self, context: Arc<AppContext>) {
+        self.context = context;
+    }
+    pub(super) fn set_scopes(&mut self, scopes: Vec<Arc<AppContext>>) {
+        self.scopes = scopes;
+    }
+    /// 提取器的配置，依次查找请求自身的扩展、handler 所在的 scope 和 `App` 上注册的配置
+    pub fn config<T: 'static>(&self) -> Option<&T> {
+        self.extensions
+            .get::<T>()
+            .or_else(|| self.scopes.iter().find_map(|scope| scope.get::<T>()))
+            .or_else(|| self.context.get::<T>())
+    }
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+    pub fn extensions_mut(&mut self) -> &mut Extensions {
+        &mut self.extensions
+    }
+}
@@ -0,0 +1,99 @@
+use super::BoxError;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: String,
+}
+impl Response {
+    pub fn new(status: u16) -> Self {
+        Self {
+            status,
+            headers: vec![],
+            body: String::new(),
+        }
+    }
+    pub fn ok() -> Self {
+        Self::new(200)
+    }
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+    pub fn with_body(mut self, body: impl Into<String>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn status(&self) -> u16 {
+        self.status
+    }
+    /// 第一个名为 `name` 的响应头，忽略大小写
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+    pub fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+    pub fn body(&self) -> &str {
+        &self.body
+    }
+}
+
+/// 要求 handler 的返回值可以转换为响应
+pub trait Responder {
+    fn respond_to(self) -> Result<Response, BoxError>;
+}
+
+#[rustfmt::skip]
+mod _impl_responder {
+    use super::*;
+
+    impl Responder for Response {
+        fn respond_to(self) -> Result<Response, BoxError> {
+            Ok(self)
+        }
+    }
+    impl Responder for () {
+        fn respond_to(self) -> Result<Response, BoxError> {
+            Ok(Response::ok())
+        }
+    }
+    impl Responder for String {
+        fn respond_to(self) -> Result<Response, BoxError> {
+            Ok(Response::ok()
+                .with_header("Content-Type", "text/plain; charset=utf-8")
+                .with_body(self))
+        }
+    }
+    impl Responder for &'static str {
+        fn respond_to(self) -> Result<Response, BoxError> {
+            self.to_string().respond_to()
+        }
+    }
+    // 指定状态码
+    impl<T> Responder for (u16, T)
+    where
+        T: Responder,
+    {
+        fn respond_to(self) -> Result<Response, BoxError> {
+            let mut response = self.1.respond_to()?;
+            response.status = self.0;
+            Ok(response)
+        }
+    }
+    // handler 返回的错误交给分发方处理
+    impl<T, E> Responder for Result<T, E>
+    where
+        T: Responder,
+        E: Into<BoxError>,
+    {
+        fn respond_to(self) -> Result<Response, BoxError> {
+            self.map_err(Into::into)?.respond_to()
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    collections::hash_map::RandomState,
+    error::Error,
+    fmt,
+    hash::{BuildHasher, Hasher},
+    rc::Rc,
+    time::Duration,
+};
+
+use super::{sleep, DispatchError, Request, Service, ServiceFuture, Trace};
+
+/// handler 失败后的重试方式，通过 `App::retry` 设置给最近注册的 handler
+///
+/// 默认每次重试都重新提取参数，见 `RetryPolicy::reuse_params`。
+/// 默认只重试 `DispatchError::Handler`，提取失败不会重试
+#[derive(Clone)]
+pub struct RetryPolicy {
+    attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    reuse_params: bool,
+    retry_if: Rc<dyn Fn(&DispatchError) -> bool>,
+}
+impl RetryPolicy {
+    /// 最多执行 `attempts` 次，包括第一次
+    pub fn new(attempts: u32) -> Self {
+        assert!(attempts > 0, "retry attempts must be positive");
+        Self {
+            attempts,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+            jitter: true,
+            reuse_params: false,
+            retry_if: Rc::new(|err| matches!(err, DispatchError::Handler(_))),
+        }
+    }
+    /// 第 n 次重试前等待 `base * 2^(n-1)`，最多等待 `max`
+    pub fn backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.base_delay = base;
+        self.max_delay = max;
+        self
+    }
+    /// 在 `[delay / 2, delay]` 中随机选择等待时间，避免同时重试，默认开启
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+    /// 重试时复用第一次提取到的参数，不再重新提取，要求 handler 通过 `App::reusable_handler` 注册
+    pub fn reuse_params(mut self, reuse: bool) -> Self {
+        self.reuse_params = reuse;
+        self
+    }
+    /// 只重试 `f` 返回 `true` 的失败
+    pub fn retry_if<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&DispatchError) -> bool + 'static,
+    {
+        self.retry_if = Rc::new(f);
+        self
+    }
+    /// 只重试 `source` 链中包含 `E` 的失败
+    pub fn retry_on<E: Error + 'static>(self) -> Self {
+        self.retry_if(|err| err.downcast_ref::<E>().is_some())
+    }
+
+    pub(super) fn reuses_params(&self) -> bool {
+        self.reuse_params
+    }
+    /// 第 `retry` 次重试前的等待时间，从 1 开始
+    fn delay(&self, retry: u32) -> Duration {
+        let factor = 2u32.saturating_pow(retry - 1);
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        if !self.jitter {
+            return delay;
+        }
+        let random = RandomState::new().build_hasher().finish();
+        delay / 2 + delay.mul_f64((random % 1024) as f64 / 2048.0)
+    }
+}
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("attempts", &self.attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("jitter", &self.jitter)
+            .field("reuse_params", &self.reuse_params)
+            .finish()
+    }
+}
+
+/// 按 `RetryPolicy` 重复调用内部的 `Service`，每次失败都记录到 `Trace`
+pub(super) struct Retry {
+    inner: Box<dyn Service>,
+    policy: RetryPolicy,
+}
+impl Retry {
+    pub(super) fn new(inner: Box<dyn Service>, policy: RetryPolicy) -> Self {
+        Self { inner, policy }
+    }
+}
+impl Service for Retry {
+    fn handle_request<'a>(&'a self, req: Request, trace: &'a Trace) -> ServiceFuture<'a> {
+        Box::pin(async move {
+            if self.policy.reuse_params {
+                trace.reuse_params();
+            }
+            let mut retry = 0;
+            loop {
+                // 每次尝试都拿到完整的请求，上一次可能已经取走了请求体
+                let error = match self.inner.handle_request(req.clone(), trace).await {
+                    Ok(response) => return Ok(response),
+                    Err(error) => error,
+                };
+                retry += 1;
+                if retry >= self.policy.attempts || !(self.policy.retry_if)(&error) {
+                    return Err(error);
+                }
+                let delay = self.policy.delay(retry);
+                trace.retry_after(delay);
+                sleep(delay).await;
+            }
+        })
+    }
+}
+
+#[test]
+fn test_backoff() {
+    let policy = RetryPolicy::new(5).backoff(Duration::from_millis(10), Duration::from_millis(30));
+    let delays: Vec<_> = (1..5)
+        .map(|n| policy.clone().jitter(false).delay(n))
+        .collect();
+    assert_eq!(delays, [10, 20, 30, 30].map(Duration::from_millis));
+    for _ in 0..32 {
+        let delay = policy.delay(2);
+        assert!(delay >= Duration::from_millis(10) && delay <= Duration::from_millis(20));
+    }
+}
@@ -0,0 +1,51 @@
+use std::{rc::Rc, sync::Arc, time::Duration};
+
+use super::{
+    info::HandlerInfo, limit::Limiter, AppContext, ErrorHandler, PanicPolicy, Request, Service,
+};
+
+/// `App::scope` 中设置的、只作用于这组 handler 的设置，没有设置的项沿用外层
+pub(super) struct Scope {
+    pub(super) context: Arc<AppContext>,
+    pub(super) default_timeout: Option<Duration>,
+    pub(super) panic_policy: Option<PanicPolicy>,
+    pub(super) error_handler: Option<ErrorHandler>,
+    /// 这组 handler 共享的限流
+    pub(super) limiters: Vec<Box<dyn Limiter>>,
+}
+
+/// 注册的一个 handler 及其所在的 scope
+pub(super) struct Entry {
+    pub(super) info: HandlerInfo,
+    pub(super) service: Box<dyn Service>,
+    /// 单独设置的超时，没有设置时依次使用 scope 和 `App` 的 `default_timeout`
+    pub(super) timeout: Option<Duration>,
+    /// 从内到外
+    pub(super) scopes: Vec<Rc<Scope>>,
+    /// 通过 `App::reusable_handler` 注册
+    pub(super) reusable_params: bool,
+}
+impl Entry {
+    pub(super) fn new(info: HandlerInfo, service: Box<dyn Service>) -> Self {
+        Self {
+            info,
+            service,
+            timeout: None,
+            scopes: vec![],
+            reusable_params: false,
+        }
+    }
+    /// 最内层设置了该项的 scope 中的值
+    pub(super) fn scoped<'a, T>(&'a self, f: impl Fn(&'a Scope) -> Option<T>) -> Option<T> {
+        self.scopes.iter().find_map(|scope| f(scope))
+    }
+    /// 交给这个 handler 的请求，带上路由捕获的路径参数和所在 scope 的配置
+    pub(super) fn request(&self, req: &Request, params: &[(&str, String)]) -> Request {
+        let mut req = params.iter().fold(req.clone(), |req, (name, value)| {
+            req.with_param(*name, value)
+        });
+        let scopes = self.scopes.iter().map(|scope| scope.context.clone());
+        req.set_scopes(scopes.collect());
+        req
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BinaryHeap,
+    future::Future,
+    pin::Pin,
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex, OnceLock, Weak,
+    },
+    task::{Context, Poll, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// 不依赖具体运行时的定时器，所有 `Sleep` 共用一个后台线程
+fn timer() -> &'static Mutex<Sender<Entry>> {
+    static TIMER: OnceLock<Mutex<Sender<Entry>>> = OnceLock::new();
+    TIMER.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        thread::Builder::new()
+            .name("type_erase-timer".to_string())
+            .spawn(move || run(rx))
+            .expect("failed to spawn timer thread");
+        Mutex::new(tx)
+    })
+}
+
+/// 按到期时间依次唤醒，没有待到期的定时器时阻塞等待新的注册
+///
+/// 条目数翻倍时清理已被丢弃的 `Sleep`，避免长超时的条目堆积到期限
+fn run(rx: Receiver<Entry>) {
+    const MIN_PRUNE: usize = 1024;
+    let mut heap: BinaryHeap<Reverse<Entry>> = BinaryHeap::new();
+    let mut prune_at = MIN_PRUNE;
+    loop {
+        if heap.len() >= prune_at {
+            heap.retain(|Reverse(entry)| entry.state.strong_count() > 0);
+            prune_at = MIN_PRUNE.max(heap.len() * 2);
+        }
+        let now = Instant::now();
+        while let Some(Reverse(entry)) = heap.peek() {
+            if entry.deadline > now {
+                break;
+            }
+            let Reverse(entry) = heap.pop().unwrap();
+            entry.fire();
+        }
+        let received = match heap.peek() {
+            Some(Reverse(entry)) => rx.recv_timeout(entry.deadline - now),
+            None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match received {
+            Ok(entry) => heap.push(Reverse(entry)),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    fired: bool,
+    waker: Option<Waker>,
+}
+
+/// 只持有弱引用，`Sleep` 被丢弃后其中的 waker 随之释放
+struct Entry {
+    deadline: Instant,
+    state: Weak<Mutex<State>>,
+}
+impl Entry {
+    fn fire(self) {
+        let state = match self.state.upgrade() {
+            Some(state) => state,
+            None => return,
+        };
+        let mut state = state.lock().unwrap();
+        state.fired = true;
+        if let Some(waker) = state.waker.take() {
+            waker.wake();
+        }
+    }
+}
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+impl Eq for Entry {}
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.deadline.cmp(&other.deadline)
+    }
+}
+
+/// 等待 `duration` 后完成，可以在任意运行时中使用
+pub fn sleep(duration: Duration) -> Sleep {
+    Sleep {
+        deadline: Instant::now() + duration,
+        state: None,
+    }
+}
+
+/// 见 [`sleep`]，第一次 `poll` 时才注册到定时器
+#[derive(Debug)]
+pub struct Sleep {
+    deadline: Instant,
+    state: Option<Arc<Mutex<State>>>,
+}
+impl Future for Sleep {
+    type Output = ();
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        if Instant::now() >= self.deadline {
+            return Poll::Ready(());
+        }
+        match &self.state {
+            Some(state) => {
+                let mut state = state.lock().unwrap();
+                if state.fired {
+                    return Poll::Ready(());
+                }
+                state.waker = Some(cx.waker().clone());
+            }
+            None => {
+                let state = Arc::new(Mutex::new(State {
+                    fired: false,
+                    waker: Some(cx.waker().clone()),
+                }));
+                let entry = Entry {
+                    deadline: self.deadline,
+                    state: Arc::downgrade(&state),
+                };
+                timer().lock().unwrap().send(entry).unwrap();
+                self.state = Some(state);
+            }
+        }
+        Poll::Pending
+    }
+}
+
+/// 在 `duration` 内没有完成时放弃 `future`，超时返回 `None`
+pub fn timeout<F: Future + Unpin>(duration: Duration, future: F) -> Timeout<F> {
+    Timeout {
+        future,
+        sleep: sleep(duration),
+    }
+}
+
+/// 见 [`timeout`]
+#[derive(Debug)]
+pub struct Timeout<F> {
+    future: F,
+    sleep: Sleep,
+}
+impl<F: Future + Unpin> Future for Timeout<F> {
+    type Output = Option<F::Output>;
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(output) = Pin::new(&mut self.future).poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        Pin::new(&mut self.sleep).poll(cx).map(|()| None)
+    }
+}
+
+#[cfg(test)]
+#[tokio::test]
+async fn test_timer() {
+    let start = Instant::now();
+    let (long, short) = (
+        sleep(Duration::from_millis(40)),
+        sleep(Duration::from_millis(10)),
+    );
+    assert_eq!(timeout(Duration::from_millis(20), long).await, None);
+    assert_eq!(timeout(Duration::from_millis(20), short).await, Some(()));
+    assert!(start.elapsed() >= Duration::from_millis(20));
+
+    // 丢弃后定时器线程不再持有它的 waker
+    let mut long = sleep(Duration::from_secs(60));
+    let mut cx = Context::from_waker(Waker::noop());
+    assert!(Pin::new(&mut long).poll(&mut cx).is_pending());
+    let state = Arc::downgrade(long.state.as_ref().unwrap());
+    drop(long);
+    assert!(state.upgrade().is_none());
+}
@@ -0,0 +1,365 @@
+//! `a=1&b=2` 形式的 urlencoded 字符串的解码，查询字符串和表单共用
+
+use std::{borrow::Cow, error::Error as StdError, fmt, vec};
+
+use serde::{
+    de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor},
+    forward_to_deserialize_any,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error(String);
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+impl StdError for Error {}
+impl de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Self(msg.to_string())
+    }
+}
+
+/// 百分号解码，`+` 视为空格，非法的 UTF-8 会被替换
+pub fn decode(s: &str) -> Cow<'_, str> {
+    if !s.contains(['%', '+']) {
+        return Cow::Borrowed(s);
+    }
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            // from_str_radix 接受 `+5` 这样的符号，需要先检查两位都是十六进制数字
+            b'%' => match bytes.get(i + 1..i + 3) {
+                Some(hex) if hex.iter().all(u8::is_ascii_hexdigit) => {
+                    decoded.push(hex_value(hex[0]) << 4 | hex_value(hex[1]));
+                    i += 2;
+                }
+                _ => decoded.push(b'%'),
+            },
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    Cow::Owned(String::from_utf8_lossy(&decoded).into_owned())
+}
+
+fn hex_value(digit: u8) -> u8 {
+    (digit as char).to_digit(16).unwrap() as u8
+}
+
+/// 按顺序解析出所有的键值对，没有 `=` 的键值为空字符串
+pub fn parse(input: &str) -> impl Iterator<Item = (Cow<'_, str>, Cow<'_, str>)> {
+    input.split('&').filter(|s| !s.is_empty()).map(|pair| {
+        let mut kv = pair.splitn(2, '=');
+        let key = kv.next().unwrap_or("");
+        let value = kv.next().unwrap_or("");
+        (decode(key), decode(value))
+    })
+}
+
+/// 反序列化为 `T`。
+///
+/// 结构体和 map 按键取值，重复的键可以收集到 `Vec` 中；
+/// 元组和 `Vec` 则按顺序得到 `(键, 值)` 对。
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, Error> {
+    T::deserialize(Deserializer { input })
+}
+
+struct Deserializer<'a> {
+    input: &'a str,
+}
+impl<'a> Deserializer<'a> {
+    /// 相同的键合并到一起，保持第一次出现的顺序
+    fn grouped(&self) -> Vec<(String, Vec<String>)> {
+        let mut groups: Vec<(String, Vec<String>)> = vec![];
+        for (key, value) in parse(self.input) {
+            match groups.iter_mut().find(|(k, _)| *k == key) {
+                Some((_, values)) => values.push(value.into_owned()),
+                None => groups.push((key.into_owned(), vec![value.into_owned()])),
+            }
+        }
+        groups
+    }
+}
+impl<'de, 'a> de::Deserializer<'de> for Deserializer<'a> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+    fn deserialize_map<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_map(MapAccess {
+            groups: self.grouped().into_iter(),
+            values: None,
+        })
+    }
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_map(visitor)
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let pairs: Vec<_> = parse(self.input)
+            .map(|(k, v)| Pair(k.into_owned(), v.into_owned()))
+            .collect();
+        visitor.visit_seq(de::value::SeqDeserializer::new(pairs.into_iter()))
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit_struct enum identifier ignored_any
+    }
+}
+
+struct MapAccess {
+    groups: vec::IntoIter<(String, Vec<String>)>,
+    values: Option<Vec<String>>,
+}
+impl<'de> de::MapAccess<'de> for MapAccess {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Error> {
+        match self.groups.next() {
+            Some((key, values)) => {
+                self.values = Some(values);
+                seed.deserialize(Value(key)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, Error> {
+        let values = self.values.take().unwrap_or_default();
+        seed.deserialize(Values(values))
+    }
+}
+
+/// 一个 `(键, 值)` 对
+struct Pair(String, String);
+impl<'de> IntoDeserializer<'de, Error> for Pair {
+    type Deserializer = Self;
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+impl<'de> de::Deserializer<'de> for Pair {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let pair = vec![Value(self.0), Value(self.1)];
+        visitor.visit_seq(de::value::SeqDeserializer::new(pair.into_iter()))
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+/// 同一个键的所有值，反序列化为序列时全部使用，否则只取第一个
+struct Values(Vec<String>);
+macro_rules! first_value {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                self.first().$method(visitor)
+            }
+        )*
+    };
+}
+impl<'de> de::Deserializer<'de> for Values {
+    type Error = Error;
+
+    first_value! {
+        deserialize_any deserialize_bool deserialize_i8 deserialize_i16 deserialize_i32
+        deserialize_i64 deserialize_i128 deserialize_u8 deserialize_u16 deserialize_u32
+        deserialize_u64 deserialize_u128 deserialize_f32 deserialize_f64 deserialize_char
+        deserialize_option deserialize_unit
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        let values = self.0.into_iter().map(Value);
+        visitor.visit_seq(de::value::SeqDeserializer::new(values))
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        self.first().deserialize_enum(name, variants, visitor)
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct tuple_struct map struct identifier ignored_any
+    }
+}
+impl Values {
+    fn first(self) -> Value {
+        Value(self.0.into_iter().next().unwrap_or_default())
+    }
+}
+
+/// 单个已解码的值，按目标类型通过 `FromStr` 解析
+struct Value(String);
+impl<'de> IntoDeserializer<'de, Error> for Value {
+    type Deserializer = Self;
+    fn into_deserializer(self) -> Self {
+        self
+    }
+}
+macro_rules! parse_value {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+                match self.0.parse() {
+                    Ok(v) => visitor.$visit(v),
+                    Err(e) => Err(de::Error::custom(format_args!("`{}`: {}", self.0, e))),
+                }
+            }
+        )*
+    };
+}
+impl<'de> de::Deserializer<'de> for Value {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_string(self.0)
+    }
+    parse_value! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+    /// 空值视为 `None`
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        if self.0.is_empty() {
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_enum(self.0.into_deserializer())
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Error> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Error> {
+        visitor.visit_unit()
+    }
+
+    forward_to_deserialize_any! {
+        str string bytes byte_buf unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[test]
+fn test_urlencoded() {
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    assert_eq!(decode("a%20b+c%2B%zz"), "a b c+%zz");
+    assert_eq!(decode("%E4%BD%A0%E5%A5%BD"), "你好");
+    assert_eq!(decode("a%+5b%-1"), "a% 5b%-1");
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Order {
+        Asc,
+        Desc,
+    }
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Filter {
+        limit: u32,
+        tag: Vec<String>,
+        order: Option<Order>,
+        offset: Option<u32>,
+    }
+    let filter: Filter = from_str("limit=10&tag=a&tag=b%20c&order=desc").unwrap();
+    assert_eq!(
+        filter,
+        Filter {
+            limit: 10,
+            tag: vec!["a".to_string(), "b c".to_string()],
+            order: Some(Order::Desc),
+            offset: None,
+        }
+    );
+    let err = from_str::<Filter>("limit=ten&tag=a").unwrap_err();
+    assert!(err.to_string().contains("`ten`"), "{}", err);
+
+    let map: HashMap<String, Vec<u32>> = from_str("a=1&b=2&a=3").unwrap();
+    assert_eq!(map["a"], [1, 3]);
+    let pairs: ((String, u32), (String, bool)) = from_str("n=1&flag=true").unwrap();
+    assert_eq!(pairs, (("n".to_string(), 1), ("flag".to_string(), true)));
+}
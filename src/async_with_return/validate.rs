@@ -0,0 +1,208 @@
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+use super::{
+    ExtractError, ExtractFuture, Form, FromRequest, FromRequestBody, Json, Query, Request, ViaBody,
+};
+
+/// 提取之后的校验，通常通过 `#[derive(Validate)]` 实现
+pub trait Validate {
+    fn validate(&self) -> Result<(), ValidationErrors>;
+}
+
+/// 一个字段违反的一条规则
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldError {
+    pub field: &'static str,
+    /// 规则名，如 `min`、`length`
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// 校验失败时所有违反的规则，不会在第一条失败时停下
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ValidationErrors(Vec<FieldError>);
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add(&mut self, field: &'static str, rule: &'static str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field,
+            rule,
+            message: message.into(),
+        });
+    }
+    pub fn errors(&self) -> &[FieldError] {
+        &self.0
+    }
+    /// 所有违反了规则的字段，按出现顺序去重
+    pub fn fields(&self) -> Vec<&'static str> {
+        let mut fields: Vec<&'static str> = vec![];
+        for error in self.0.iter() {
+            if !fields.contains(&error.field) {
+                fields.push(error.field);
+            }
+        }
+        fields
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+    /// 没有错误时为 `Ok(())`
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            write!(f, "`{}` {}", error.field, error.message)?;
+        }
+        Ok(())
+    }
+}
+impl Error for ValidationErrors {}
+
+/// `#[validate(length(..))]` 和 `#[validate(non_empty)]` 使用的长度，字符串按字符计
+pub trait Length {
+    fn length(&self) -> usize;
+}
+
+#[rustfmt::skip]
+mod _impl_length {
+    use super::*;
+
+    impl Length for str {
+        fn length(&self) -> usize { self.chars().count() }
+    }
+    impl Length for String {
+        fn length(&self) -> usize { self.as_str().length() }
+    }
+    impl<T> Length for [T] {
+        fn length(&self) -> usize { self.len() }
+    }
+    impl<T> Length for Vec<T> {
+        fn length(&self) -> usize { self.len() }
+    }
+    impl<K, V, S> Length for HashMap<K, V, S> {
+        fn length(&self) -> usize { self.len() }
+    }
+    impl<T: Length + ?Sized> Length for &T {
+        fn length(&self) -> usize { (**self).length() }
+    }
+}
+
+/// 先提取 `T`，再校验，校验失败时返回 `ExtractError::Invalid`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Valid<T>(pub T);
+impl<T> Valid<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+impl<T> Deref for Valid<T> {
+    type Target = T;
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+impl<T> DerefMut for Valid<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+impl<T> FromRequest for Valid<T>
+where
+    T: FromRequest + Validate,
+{
+    type Error = ExtractError;
+    fn from_request(req: &Request) -> Result<Self, Self::Error> {
+        let value = T::from_request(req).map_err(Into::into)?;
+        value.validate().map_err(ExtractError::Invalid)?;
+        Ok(Valid(value))
+    }
+}
+// `Valid<Json<T>>` 等取走请求体的提取
+impl<T, M> FromRequestBody<ViaBody<M>> for Valid<T>
+where
+    T: FromRequestBody<ViaBody<M>> + Validate + 'static,
+{
+    fn from_request_body(req: &mut Request) -> ExtractFuture<'_, Self> {
+        Box::pin(async move {
+            let value = T::from_request_body(req).await?;
+            value.validate().map_err(ExtractError::Invalid)?;
+            Ok(Valid(value))
+        })
+    }
+}
+
+#[rustfmt::skip]
+mod _impl_validate {
+    use super::*;
+
+    // 让 `Valid<Json<T>>` 等直接校验内部的值
+    impl<T: Validate> Validate for Json<T> {
+        fn validate(&self) -> Result<(), ValidationErrors> { self.0.validate() }
+    }
+    impl<T: Validate> Validate for Form<T> {
+        fn validate(&self) -> Result<(), ValidationErrors> { self.0.validate() }
+    }
+    impl<T: Validate> Validate for Query<T> {
+        fn validate(&self) -> Result<(), ValidationErrors> { self.0.validate() }
+    }
+}
+
+#[test]
+fn test_valid() {
+    use super::Validate;
+    use serde::Deserialize;
+
+    fn no_spaces(s: &str) -> Result<(), String> {
+        if s.contains(' ') {
+            Err("must not contain spaces".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct Signup {
+        #[validate(length(min = 3, max = 8), custom = "no_spaces")]
+        name: String,
+        #[validate(min = 18, max = 150)]
+        age: u32,
+        #[serde(default)]
+        #[validate(non_empty)]
+        tags: Vec<String>,
+    }
+
+    let req = Request::new("").with_uri("/?name=%E5%BC%A0%E4%B8%89%E4%B8%B0&age=30&tags=a");
+    let Valid(Query(signup)) = Valid::<Query<Signup>>::from_request(&req).unwrap();
+    assert_eq!((signup.name.as_str(), signup.age), ("张三丰", 30));
+
+    // 所有违反的规则都会报告
+    let req = Request::new("").with_uri("/?name=a+b&age=12");
+    let err = match Valid::<Query<Signup>>::from_request(&req).unwrap_err() {
+        ExtractError::Invalid(err) => err,
+        err => panic!("unexpected error: {}", err),
+    };
+    assert_eq!(err.fields(), ["name", "age", "tags"]);
+    let rules: Vec<_> = err.errors().iter().map(|e| e.rule).collect();
+    assert_eq!(rules, ["custom", "min", "non_empty"]);
+    assert_eq!(
+        err.to_string(),
+        "`name` must not contain spaces; `age` must be at least 18; `tags` must not be empty"
+    );
+}
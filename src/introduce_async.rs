@@ -36,6 +36,7 @@ trait Handler<T>: 'static {
 mod _impl_handler {
     use super::*;
     // delegate
+    #[allow(clippy::unused_unit)]
     impl<F> Handler<()> for F where F: Fn() -> () + 'static {
         fn call(&self, params: ()) {
             (self)()
@@ -55,16 +56,7 @@ mod _impl_handler {
             }
         };
     }
-    f!((T1), (0));
-    f!((T1, T2), (0, 1));
-    f!((T1, T2, T3), (0, 1, 2));
-    f!((T1, T2, T3, T4), (0, 1, 2, 3));
-    f!((T1, T2, T3, T4, T5), (0, 1, 2, 3, 4));
-    f!((T1, T2, T3, T4, T5, T6), (0, 1, 2, 3, 4, 5));
-    f!((T1, T2, T3, T4, T5, T6, T7), (0, 1, 2, 3, 4, 5, 6));
-    f!((T1, T2, T3, T4, T5, T6, T7, T8), (0, 1, 2, 3, 4, 5, 6, 7));
-    f!((T1, T2, T3, T4, T5, T6, T7, T8, T9), (0, 1, 2, 3, 4, 5, 6, 7, 8));
-    f!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9));
+    all_tuples!(f);
 }
 
 /// 这里将函数指针的 T 提到类型参数中
@@ -115,6 +107,7 @@ mod _impl_from_request {
             req.s.parse().unwrap()
         }
     }
+    #[allow(clippy::unused_unit)]
     impl FromRequest for () {
         fn from_request(req: &Request) -> Self {
             ()
@@ -122,7 +115,7 @@ mod _impl_from_request {
     }
     // propagate
     macro_rules! f {
-        ($($Ts:tt),*) => {
+        (($($Ts:ident),*), ($($Ns:tt),*)) => {
             impl< $($Ts,)* > FromRequest for ( $($Ts,)* )
             where
                 $(
@@ -139,16 +132,7 @@ mod _impl_from_request {
             }
         };
     }
-    f!(T1);
-    f!(T1, T2);
-    f!(T1, T2, T3);
-    f!(T1, T2, T3, T4);
-    f!(T1, T2, T3, T4, T5);
-    f!(T1, T2, T3, T4, T5, T6);
-    f!(T1, T2, T3, T4, T5, T6, T7);
-    f!(T1, T2, T3, T4, T5, T6, T7, T8);
-    f!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
-    f!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+    all_tuples!(f);
 }
 
 impl Request {
@@ -1,5 +1,12 @@
 #![allow(unused)]
 
+// 让过程宏生成的 `::type_erase::...` 路径在本 crate 内也能使用
+extern crate self as type_erase;
+
+#[macro_use]
+mod arity;
+
 mod start_simple;
 mod second_try;
 mod introduce_async;
+pub mod async_with_return;
@@ -1,11 +1,39 @@
-use std::marker::PhantomData;
+use std::{
+    any::Any,
+    marker::PhantomData,
+    panic::{self, AssertUnwindSafe},
+};
+
+/// handler panic 时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum PanicPolicy {
+    /// 捕获后记为这个 handler 的错误，默认
+    #[default]
+    Isolate,
+    /// 不捕获，便于在测试中定位
+    Propagate,
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
 
 struct App {
     services: Vec<Box<dyn Service>>,
+    panic_policy: PanicPolicy,
 }
 impl App {
     pub fn new() -> Self {
-        Self { services: vec![] }
+        Self {
+            services: vec![],
+            panic_policy: PanicPolicy::default(),
+        }
     }
 
     pub fn handler<F, T>(mut self, f: F) -> Self
@@ -18,10 +46,26 @@ impl App {
         self
     }
 
-    pub fn dispatch(&self, req: Request) {
-        for f in self.services.iter() {
-            f.handle_request(&req);
-        }
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = policy;
+        self
+    }
+
+    /// 默认每个 handler 的 panic 都被单独捕获，不影响后续的 handler
+    pub fn dispatch(&self, req: Request) -> Vec<Result<(), String>> {
+        self.services
+            .iter()
+            .map(|f| match self.panic_policy {
+                PanicPolicy::Isolate => {
+                    panic::catch_unwind(AssertUnwindSafe(|| f.handle_request(&req)))
+                        .map_err(panic_message)
+                }
+                PanicPolicy::Propagate => {
+                    f.handle_request(&req);
+                    Ok(())
+                }
+            })
+            .collect()
     }
 }
 
@@ -36,6 +80,7 @@ trait Handler<T>: 'static {
 mod _impl_handler {
     use super::*;
     // delegate
+    #[allow(clippy::unused_unit)]
     impl<F> Handler<()> for F where F: Fn() -> () + 'static {
         fn call(&self, params: ()) {
             (self)()
@@ -55,16 +100,7 @@ mod _impl_handler {
             }
         };
     }
-    f!((T1), (0));
-    f!((T1, T2), (0, 1));
-    f!((T1, T2, T3), (0, 1, 2));
-    f!((T1, T2, T3, T4), (0, 1, 2, 3));
-    f!((T1, T2, T3, T4, T5), (0, 1, 2, 3, 4));
-    f!((T1, T2, T3, T4, T5, T6), (0, 1, 2, 3, 4, 5));
-    f!((T1, T2, T3, T4, T5, T6, T7), (0, 1, 2, 3, 4, 5, 6));
-    f!((T1, T2, T3, T4, T5, T6, T7, T8), (0, 1, 2, 3, 4, 5, 6, 7));
-    f!((T1, T2, T3, T4, T5, T6, T7, T8, T9), (0, 1, 2, 3, 4, 5, 6, 7, 8));
-    f!((T1, T2, T3, T4, T5, T6, T7, T8, T9, T10), (0, 1, 2, 3, 4, 5, 6, 7, 8, 9));
+    all_tuples!(f);
 }
 
 /// 这里将函数指针的 T 提到类型参数中
@@ -115,6 +151,7 @@ mod _impl_from_request {
             req.s.parse().unwrap()
         }
     }
+    #[allow(clippy::unused_unit)]
     impl FromRequest for () {
         fn from_request(req: &Request) -> Self {
             ()
@@ -122,7 +159,7 @@ mod _impl_from_request {
     }
     // propagate
     macro_rules! f {
-        ($($Ts:tt),*) => {
+        (($($Ts:ident),*), ($($Ns:tt),*)) => {
             impl< $($Ts,)* > FromRequest for ( $($Ts,)* )
             where
                 $(
@@ -139,16 +176,7 @@ mod _impl_from_request {
             }
         };
     }
-    f!(T1);
-    f!(T1, T2);
-    f!(T1, T2, T3);
-    f!(T1, T2, T3, T4);
-    f!(T1, T2, T3, T4, T5);
-    f!(T1, T2, T3, T4, T5, T6);
-    f!(T1, T2, T3, T4, T5, T6, T7);
-    f!(T1, T2, T3, T4, T5, T6, T7, T8);
-    f!(T1, T2, T3, T4, T5, T6, T7, T8, T9);
-    f!(T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+    all_tuples!(f);
 }
 
 impl Request {
@@ -200,6 +228,42 @@ fn test_second_try() {
         .handler(MockHandler::f0)
         .handler(|s: String| MockHandler::f1(s))
         .handler(MockHandler::f2)
-        .handler(MockHandler::f3);
+        .handler(MockHandler::f3)
+        // 最多支持 16 个参数
+        .handler(
+            |n1: u32,
+             n2: u32,
+             n3: u32,
+             n4: u32,
+             n5: u32,
+             n6: u32,
+             n7: u32,
+             n8: u32,
+             n9: u32,
+             n10: u32,
+             n11: u32,
+             n12: u32,
+             n13: u32,
+             n14: u32,
+             n15: u32,
+             s: String| {
+                assert_eq!(n1 + n15, 246);
+                assert_eq!(s, "123");
+            },
+        );
     app.dispatch(Request::new("123"));
+
+    // u32 解析失败时 panic，只影响这一个 handler
+    let app = App::new()
+        .handler(|n: u32| {})
+        .handler(|s: String| assert_eq!(s, "abc"));
+    let results = app.dispatch(Request::new("abc"));
+    assert!(results[0].as_ref().unwrap_err().contains("ParseIntError"));
+    assert!(results[1].is_ok());
+
+    let app = App::new()
+        .handler(|n: u32| {})
+        .panic_policy(PanicPolicy::Propagate);
+    let dispatch = AssertUnwindSafe(|| app.dispatch(Request::new("abc")));
+    assert!(panic::catch_unwind(dispatch).is_err());
 }
@@ -1,6 +1,9 @@
+// 保留 `-> ()` 以便与后面带参数的版本对照
+#[allow(clippy::unused_unit)]
 struct App {
     handlers: Vec<Box<dyn Fn() -> ()>>,
 }
+#[allow(clippy::unused_unit)]
 impl App {
     pub fn new() -> Self {
         Self { handlers: vec![] }
@@ -17,6 +20,7 @@ impl App {
 }
 
 #[test]
+#[allow(clippy::redundant_closure)]
 fn test_start_simple() {
     use mockall::*;
 